@@ -0,0 +1,170 @@
+//! Bounding boxes of curves, worked out from the dragon curve's
+//! self-similarity instead of by drawing it.
+//!
+//! The first `2^(k + 1)` segments of the dragon curve are the first `2^k`,
+//! followed by the same segments backwards, turned a quarter turn about the
+//! point where they end. Quarter turns take bounding boxes to bounding boxes,
+//! so the box of any number of segments comes out of `O(log n)` boxes of
+//! whole halves and their ends.
+
+/// An axis-aligned box holding a set of points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min: (f64, f64),
+    pub max: (f64, f64),
+}
+
+impl Bounds {
+    /// The box around a single point.
+    pub fn point(point: (f64, f64)) -> Bounds {
+        Bounds {
+            min: point,
+            max: point,
+        }
+    }
+
+    /// The smallest box holding both boxes.
+    pub fn union(self, other: Bounds) -> Bounds {
+        Bounds {
+            min: (self.min.0.min(other.min.0), self.min.1.min(other.min.1)),
+            max: (self.max.0.max(other.max.0), self.max.1.max(other.max.1)),
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max.0 - self.min.0
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max.1 - self.min.1
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (
+            (self.min.0 + self.max.0) / 2.0,
+            (self.min.1 + self.max.1) / 2.0,
+        )
+    }
+
+    /// The box around this one's corners after `f`, which is exact when `f`
+    /// only moves, scales and turns by quarter turns.
+    pub fn map(self, f: impl Fn((f64, f64)) -> (f64, f64)) -> Bounds {
+        let corners = [
+            self.min,
+            (self.max.0, self.min.1),
+            (self.min.0, self.max.1),
+            self.max,
+        ];
+        corners
+            .into_iter()
+            .map(|corner| Bounds::point(f(corner)))
+            .reduce(Bounds::union)
+            .unwrap()
+    }
+}
+
+/// Bounding box of the first `segments` segments of the dragon curve, which
+/// starts at the origin heading along `(1, 0)` with segments one unit long.
+pub fn dragon_extent(segments: u64) -> Bounds {
+    let levels = (u64::BITS - segments.saturating_sub(1).leading_zeros()) as usize;
+    let halves = Halves::new(levels);
+    halves.prefix(levels, segments)
+}
+
+/// The box and end point of every whole power-of-two run of segments.
+struct Halves {
+    ends: Vec<(f64, f64)>, // point after `2^k` segments
+    boxes: Vec<Bounds>,    // box of the first `2^k` segments
+}
+
+impl Halves {
+    fn new(levels: usize) -> Halves {
+        let mut ends = vec![(1.0, 0.0)];
+        let mut boxes = vec![Bounds::point((0.0, 0.0)).union(Bounds::point((1.0, 0.0)))];
+        for k in 0..levels {
+            let end = ends[k];
+            boxes.push(boxes[k].union(Halves::second(end, boxes[k])));
+            ends.push(quarter_turn(end, (0.0, 0.0)));
+        }
+        Halves { ends, boxes }
+    }
+
+    /// Where a box of the first half lands in the second half.
+    fn second(end: (f64, f64), bounds: Bounds) -> Bounds {
+        bounds.map(|point| quarter_turn(end, point))
+    }
+
+    /// Box of the first `n` segments out of `2^k`.
+    fn prefix(&self, k: usize, n: u64) -> Bounds {
+        if n == 0 {
+            return Bounds::point((0.0, 0.0));
+        }
+        if k == 0 {
+            return self.boxes[0];
+        }
+        let half = 1 << (k - 1);
+        if n <= half {
+            self.prefix(k - 1, n)
+        } else {
+            let rest = self.suffix(k - 1, n - half);
+            self.boxes[k - 1].union(Halves::second(self.ends[k - 1], rest))
+        }
+    }
+
+    /// Box of the last `m` segments out of `2^k`.
+    fn suffix(&self, k: usize, m: u64) -> Bounds {
+        if m == 0 {
+            return Bounds::point(self.ends[k]);
+        }
+        if k == 0 {
+            return self.boxes[0];
+        }
+        let half = 1 << (k - 1);
+        let end = self.ends[k - 1];
+        if m <= half {
+            Halves::second(end, self.prefix(k - 1, m))
+        } else {
+            Halves::second(end, self.boxes[k - 1]).union(self.suffix(k - 1, m - half))
+        }
+    }
+}
+
+/// `point` turned a quarter turn about `center`, the way the second half of
+/// the dragon curve is turned from the first.
+fn quarter_turn(center: (f64, f64), point: (f64, f64)) -> (f64, f64) {
+    let (x, y) = (point.0 - center.0, point.1 - center.1);
+    (center.0 + y, center.1 - x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paperfolding::Folds;
+
+    #[test]
+    fn dragon_extent_matches_walking() {
+        let folds = Folds::dragon();
+        let mut walked = Bounds::point((0.0, 0.0));
+        for segments in 1..=1100 {
+            walked = walked.union(Bounds::point(folds.point_at(segments, 90.0).0));
+            assert_eq!(dragon_extent(segments), walked, "{segments} segments");
+        }
+    }
+
+    #[test]
+    fn dragon_extent_of_nothing_is_the_start() {
+        assert_eq!(dragon_extent(0), Bounds::point((0.0, 0.0)));
+    }
+
+    #[test]
+    fn maps_by_quarter_turns() {
+        let bounds = Bounds::point((1.0, 2.0)).union(Bounds::point((4.0, 3.0)));
+        let turned = bounds.map(|point| quarter_turn((0.0, 0.0), point));
+        assert_eq!(
+            turned,
+            Bounds::point((2.0, -1.0)).union(Bounds::point((3.0, -4.0)))
+        );
+        assert_eq!((bounds.width(), bounds.height()), (3.0, 1.0));
+        assert_eq!(bounds.center(), (2.5, 2.5));
+    }
+}
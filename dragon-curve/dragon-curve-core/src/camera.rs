@@ -0,0 +1,173 @@
+//! A pan and zoom view onto the curves, which are stepped in world
+//! coordinates where one pixel is one unit.
+
+use crate::curve::{Config, State};
+use crate::density::Density;
+use crate::framebuffer::Framebuffer;
+use crate::stroke::{draw_line, Stroke};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    offset: (f64, f64), // world position of the framebuffer's top left corner
+    scale: f64,         // framebuffer pixels per world pixel
+}
+
+impl Default for Camera {
+    /// Shows world pixels one to one, with the origin in the top left corner.
+    fn default() -> Camera {
+        Camera {
+            offset: (0.0, 0.0),
+            scale: 1.0,
+        }
+    }
+}
+
+impl Camera {
+    pub fn offset(&self) -> (f64, f64) {
+        self.offset
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn to_screen(&self, world: (f64, f64)) -> (f64, f64) {
+        (
+            (world.0 - self.offset.0) * self.scale,
+            (world.1 - self.offset.1) * self.scale,
+        )
+    }
+
+    pub fn to_world(&self, screen: (f64, f64)) -> (f64, f64) {
+        (
+            screen.0 / self.scale + self.offset.0,
+            screen.1 / self.scale + self.offset.1,
+        )
+    }
+
+    /// Moves the view so the world follows the mouse by `delta` screen pixels.
+    pub fn pan(&mut self, delta: (f64, f64)) {
+        self.offset.0 -= delta.0 / self.scale;
+        self.offset.1 -= delta.1 / self.scale;
+    }
+
+    /// Zooms in by `factor` (or out, below 1), keeping the world point under
+    /// `screen` where it is.
+    pub fn zoom(&mut self, factor: f64, screen: (f64, f64)) {
+        let anchor = self.to_world(screen);
+        self.scale = (self.scale * factor).clamp(1.0 / 64.0, 64.0);
+        self.offset = (
+            anchor.0 - screen.0 / self.scale,
+            anchor.1 - screen.1 / self.scale,
+        );
+    }
+
+    /// The framebuffer pixels a world pixel lands on, more than one when
+    /// zoomed in.
+    pub fn cover(&self, position: (isize, isize)) -> impl Iterator<Item = (isize, isize)> {
+        let (x, y) = (position.0 as f64, position.1 as f64);
+        let (left, top) = self.to_screen((x, y));
+        let (right, bottom) = self.to_screen((x + 1.0, y + 1.0));
+        let (left, top) = (left.floor() as isize, top.floor() as isize);
+        let right = (right.floor() as isize).max(left + 1);
+        let bottom = (bottom.floor() as isize).max(top + 1);
+        (top..bottom).flat_map(move |y| (left..right).map(move |x| (x, y)))
+    }
+}
+
+/// Advances `state` by one pixel and draws that step into `framebuffer` as
+/// seen through `camera`. Smooth strokes are drawn at the camera's scale
+/// instead of as blown up pixels.
+pub fn update_in_view(
+    config: &Config,
+    camera: &Camera,
+    framebuffer: &mut impl Framebuffer,
+    state: &mut State,
+) {
+    let Some((span, color)) = state.step_span(config) else {
+        return;
+    };
+    match state.stroke() {
+        Stroke::Pixels => {
+            for pixel in camera.cover(span.pixel()) {
+                framebuffer.blend(pixel, color, state.blend(), state.opacity());
+            }
+        }
+        Stroke::Smooth { width } => {
+            // curve points are pixel centers on screen as well as in the world
+            let span = span.map(|point| {
+                let (x, y) = camera.to_screen((point.0 + 0.5, point.1 + 0.5));
+                (x - 0.5, y - 0.5)
+            });
+            let width = width * camera.scale;
+            draw_line(
+                framebuffer,
+                &span,
+                width,
+                color,
+                state.blend(),
+                state.opacity(),
+            );
+        }
+    }
+}
+
+/// Advances `state` by one pixel and counts that pixel in `density` as seen
+/// through `camera`.
+pub fn accumulate_in_view(
+    config: &Config,
+    camera: &Camera,
+    density: &mut Density,
+    state: &mut State,
+) {
+    if let Some((position, color)) = state.step(config) {
+        for pixel in camera.cover(position) {
+            density.add(pixel, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_between_world_and_screen() {
+        let mut camera = Camera::default();
+        camera.zoom(4.0, (0.0, 0.0));
+        camera.pan((8.0, -4.0));
+        assert_eq!(camera.offset(), (-2.0, 1.0));
+        assert_eq!(camera.to_screen((0.0, 0.0)), (8.0, -4.0));
+        assert_eq!(camera.to_world(camera.to_screen((3.5, -7.0))), (3.5, -7.0));
+    }
+
+    #[test]
+    fn zooms_about_the_mouse() {
+        let mut camera = Camera::default();
+        camera.pan((-10.0, 20.0));
+        let under_mouse = camera.to_world((30.0, 40.0));
+        camera.zoom(2.0, (30.0, 40.0));
+        assert_eq!(camera.scale(), 2.0);
+        assert_eq!(camera.to_world((30.0, 40.0)), under_mouse);
+
+        camera.zoom(1000.0, (0.0, 0.0));
+        assert_eq!(camera.scale(), 64.0);
+        camera.zoom(1e-9, (0.0, 0.0));
+        assert_eq!(camera.scale(), 1.0 / 64.0);
+    }
+
+    #[test]
+    fn covers_every_pixel_a_world_pixel_lands_on() {
+        let cover = |camera: &Camera, position| camera.cover(position).collect::<Vec<_>>();
+        let mut camera = Camera::default();
+        assert_eq!(cover(&camera, (3, -2)), [(3, -2)]);
+
+        camera.zoom(2.0, (0.0, 0.0));
+        assert_eq!(cover(&camera, (1, 0)), [(2, 0), (3, 0), (2, 1), (3, 1)]);
+
+        // zoomed out, neighbours share a pixel
+        camera.zoom(0.25, (0.0, 0.0));
+        assert_eq!(cover(&camera, (4, 5)), [(2, 2)]);
+        assert_eq!(cover(&camera, (5, 5)), [(2, 2)]);
+    }
+}
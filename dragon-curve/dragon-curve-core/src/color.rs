@@ -0,0 +1,346 @@
+use std::f64::consts::{PI, TAU};
+use std::fmt;
+use std::str::FromStr;
+
+/// An RGB color with each channel in `0.0..=1.0`.
+pub type Color = (f64, f64, f64);
+
+// colors
+pub const BLACK: Color = (0.0, 0.0, 0.0);
+pub const WHITE: Color = (1.0, 1.0, 1.0);
+pub const RED: Color = (1.0, 0.0, 0.0);
+pub const YELLOW: Color = (1.0, 1.0, 0.0);
+pub const GREEN: Color = (0.0, 1.0, 0.0);
+pub const CYAN: Color = (0.0, 1.0, 1.0);
+pub const BLUE: Color = (0.0, 0.0, 1.0);
+pub const MAGENTA: Color = (1.0, 0.0, 1.0);
+
+/// Packs a color into the `0xAARRGGBB` layout minifb expects.
+pub fn to_bgra(color: Color) -> u32 {
+    let r: u32 = (color.0 * 255.99) as u32;
+    let g: u32 = (color.1 * 255.99) as u32;
+    let b: u32 = (color.2 * 255.99) as u32;
+    255 << 24 | r << 16 | g << 8 | b
+}
+
+/// Packs a color into the `[r, g, b, a]` byte layout pixels expects.
+pub fn to_rgba(color: Color) -> [u8; 4] {
+    let r: u8 = (color.0 * 255.99) as u8;
+    let g: u8 = (color.1 * 255.99) as u8;
+    let b: u8 = (color.2 * 255.99) as u8;
+    let a: u8 = 0xff;
+    [r, g, b, a]
+}
+
+/// Unpacks a color from the `0xAARRGGBB` layout minifb uses.
+pub fn from_bgra(pixel: u32) -> Color {
+    let channel = |shift: u32| ((pixel >> shift) & 0xff) as f64 / 255.0;
+    (channel(16), channel(8), channel(0))
+}
+
+/// Unpacks a color from the `[r, g, b, a]` byte layout pixels uses.
+pub fn from_rgba(pixel: [u8; 4]) -> Color {
+    (
+        pixel[0] as f64 / 255.0,
+        pixel[1] as f64 / 255.0,
+        pixel[2] as f64 / 255.0,
+    )
+}
+
+pub fn lerp_f64(u: f64, v: f64, t: f64) -> f64 {
+    v * t + u * (1.0 - t)
+}
+
+pub fn lerp_color(u: Color, v: Color, t: f64) -> Color {
+    (
+        lerp_f64(u.0, v.0, t),
+        lerp_f64(u.1, v.1, t),
+        lerp_f64(u.2, v.2, t),
+    )
+}
+
+/// The space gradient colors are blended in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Straight on the stored sRGB values, which darkens and dulls the
+    /// middle of most blends.
+    #[default]
+    Srgb,
+    /// On light intensities, undoing the sRGB gamma curve first.
+    LinearRgb,
+    /// In OKLab, where equal steps look about equally different.
+    Oklab,
+    /// In OKLab's polar form, going the short way around the hue circle.
+    Oklch,
+    /// In hue, saturation and value, going the short way around the hue
+    /// circle.
+    Hsv,
+}
+
+/// Names accepted when parsing a [`ColorSpace`].
+pub const COLOR_SPACE_NAMES: [&str; 5] = ["srgb", "linear-rgb", "oklab", "oklch", "hsv"];
+
+impl ColorSpace {
+    /// Blends from `u` at `t = 0` to `v` at `t = 1` in this space.
+    pub fn lerp(self, u: Color, v: Color, t: f64) -> Color {
+        let color = match self {
+            ColorSpace::Srgb => lerp_color(u, v, t),
+            ColorSpace::LinearRgb => {
+                linear_to_srgb(lerp_color(srgb_to_linear(u), srgb_to_linear(v), t))
+            }
+            ColorSpace::Oklab => oklab_to_srgb(lerp_color(srgb_to_oklab(u), srgb_to_oklab(v), t)),
+            ColorSpace::Oklch => {
+                let (u, v) = (to_polar(srgb_to_oklab(u)), to_polar(srgb_to_oklab(v)));
+                oklab_to_srgb(from_polar(lerp_polar(u, v, t)))
+            }
+            ColorSpace::Hsv => {
+                let (u, v) = (srgb_to_hsv(u), srgb_to_hsv(v));
+                // saturation plays the part of chroma, with hue in radians
+                let (value, s, h) = lerp_polar((u.2, u.1, u.0 * TAU), (v.2, v.1, v.0 * TAU), t);
+                hsv_to_srgb(((h / TAU).rem_euclid(1.0), s, value))
+            }
+        };
+        (
+            color.0.clamp(0.0, 1.0),
+            color.1.clamp(0.0, 1.0),
+            color.2.clamp(0.0, 1.0),
+        )
+    }
+}
+
+impl FromStr for ColorSpace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ColorSpace, String> {
+        match s.trim() {
+            "srgb" => Ok(ColorSpace::Srgb),
+            "linear-rgb" => Ok(ColorSpace::LinearRgb),
+            "oklab" => Ok(ColorSpace::Oklab),
+            "oklch" => Ok(ColorSpace::Oklch),
+            "hsv" => Ok(ColorSpace::Hsv),
+            s => Err(format!(
+                "unknown color space `{s}`, expected one of: {}",
+                COLOR_SPACE_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ColorSpace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ColorSpace::Srgb => "srgb",
+            ColorSpace::LinearRgb => "linear-rgb",
+            ColorSpace::Oklab => "oklab",
+            ColorSpace::Oklch => "oklch",
+            ColorSpace::Hsv => "hsv",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Blends `(lightness, chroma, hue)` triples, turning the short way around
+/// and keeping the other color's hue if one of them has none.
+fn lerp_polar(u: Color, v: Color, t: f64) -> Color {
+    let (mut hu, mut hv) = (u.2, v.2);
+    if u.1 < 1e-6 {
+        hu = hv;
+    }
+    if v.1 < 1e-6 {
+        hv = hu;
+    }
+    let mut dh = (hv - hu).rem_euclid(TAU);
+    if dh > PI {
+        dh -= TAU;
+    }
+    (lerp_f64(u.0, v.0, t), lerp_f64(u.1, v.1, t), hu + dh * t)
+}
+
+fn to_polar((l, a, b): Color) -> Color {
+    (l, a.hypot(b), b.atan2(a))
+}
+
+fn from_polar((l, c, h): Color) -> Color {
+    (l, c * h.cos(), c * h.sin())
+}
+
+fn srgb_to_linear(color: Color) -> Color {
+    let f = |c: f64| {
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    (f(color.0), f(color.1), f(color.2))
+}
+
+fn linear_to_srgb(color: Color) -> Color {
+    let f = |c: f64| {
+        if c <= 0.0031308 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    };
+    (f(color.0), f(color.1), f(color.2))
+}
+
+// OKLab matrices from https://bottosson.github.io/posts/oklab/
+fn srgb_to_oklab(color: Color) -> Color {
+    let (r, g, b) = srgb_to_linear(color);
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    (
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    )
+}
+
+fn oklab_to_srgb((lightness, a, b): Color) -> Color {
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    let linear = (
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    );
+    // out-of-gamut blends come back slightly negative
+    linear_to_srgb((linear.0.max(0.0), linear.1.max(0.0), linear.2.max(0.0)))
+}
+
+/// Returns `(hue, saturation, value)` with hue in turns.
+fn srgb_to_hsv((r, g, b): Color) -> Color {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    (hue / 6.0, saturation, max)
+}
+
+fn hsv_to_srgb((hue, saturation, value): Color) -> Color {
+    let h = hue * 6.0;
+    let c = value * saturation;
+    let x = c * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = value - c;
+    (r + m, g + m, b + m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPACES: [ColorSpace; 5] = [
+        ColorSpace::Srgb,
+        ColorSpace::LinearRgb,
+        ColorSpace::Oklab,
+        ColorSpace::Oklch,
+        ColorSpace::Hsv,
+    ];
+
+    /// Far closer than one step of an 8-bit channel, but loose enough for
+    /// the OKLab matrices, which are only given to 10 digits.
+    fn assert_close(u: Color, v: Color) {
+        let distance = (u.0 - v.0)
+            .abs()
+            .max((u.1 - v.1).abs())
+            .max((u.2 - v.2).abs());
+        assert!(distance < 1e-5, "{u:?} != {v:?}");
+    }
+
+    /// A grid of colors through the sRGB cube.
+    fn colors() -> impl Iterator<Item = Color> {
+        let levels = [0.0, 0.03, 0.2, 0.5, 0.8, 1.0];
+        levels.into_iter().flat_map(move |r| {
+            levels
+                .into_iter()
+                .flat_map(move |g| levels.into_iter().map(move |b| (r, g, b)))
+        })
+    }
+
+    #[test]
+    fn conversions_round_trip() {
+        for color in colors() {
+            assert_close(linear_to_srgb(srgb_to_linear(color)), color);
+            assert_close(oklab_to_srgb(srgb_to_oklab(color)), color);
+            assert_close(
+                oklab_to_srgb(from_polar(to_polar(srgb_to_oklab(color)))),
+                color,
+            );
+            assert_close(hsv_to_srgb(srgb_to_hsv(color)), color);
+        }
+    }
+
+    #[test]
+    fn oklab_reference_values() {
+        assert_close(srgb_to_oklab(WHITE), (1.0, 0.0, 0.0));
+        assert_close(srgb_to_oklab(BLACK), (0.0, 0.0, 0.0));
+        let red = srgb_to_oklab(RED);
+        assert!((red.0 - 0.627955).abs() < 1e-5);
+        assert!((red.1 - 0.224863).abs() < 1e-5);
+        assert!((red.2 - 0.125846).abs() < 1e-5);
+    }
+
+    #[test]
+    fn blends_start_and_end_on_their_colors() {
+        for space in SPACES {
+            for (u, v) in [(RED, CYAN), (BLACK, YELLOW), ((0.2, 0.5, 0.8), WHITE)] {
+                assert_close(space.lerp(u, v, 0.0), u);
+                assert_close(space.lerp(u, v, 1.0), v);
+            }
+        }
+    }
+
+    #[test]
+    fn hue_blends_go_the_short_way_around() {
+        // red and magenta are a sixth of a turn apart, across hue 0
+        assert_close(ColorSpace::Hsv.lerp(RED, MAGENTA, 0.5), (1.0, 0.0, 0.5));
+        // a gray has no hue, so only the other color's is used
+        let halfway = ColorSpace::Hsv.lerp((0.5, 0.5, 0.5), BLUE, 0.5);
+        assert!(halfway.2 > halfway.0 && halfway.0 == halfway.1);
+    }
+
+    #[test]
+    fn linear_blends_are_brighter_in_the_middle() {
+        let srgb = ColorSpace::Srgb.lerp(BLACK, WHITE, 0.5);
+        let linear = ColorSpace::LinearRgb.lerp(BLACK, WHITE, 0.5);
+        assert_close(srgb, (0.5, 0.5, 0.5));
+        assert!(linear.0 > 0.7);
+    }
+
+    #[test]
+    fn spaces_parse_and_display() {
+        for (space, name) in SPACES.into_iter().zip(COLOR_SPACE_NAMES) {
+            assert_eq!(space.to_string(), name);
+            assert_eq!(name.parse::<ColorSpace>(), Ok(space));
+        }
+        assert!("lab".parse::<ColorSpace>().is_err());
+    }
+
+    #[test]
+    fn packing_round_trips() {
+        let color = from_rgba([12, 34, 250, 255]);
+        assert_eq!(to_rgba(color), [12, 34, 250, 255]);
+        assert_eq!(to_bgra(from_bgra(0xff0c22fa)), 0xff0c22fa);
+    }
+}
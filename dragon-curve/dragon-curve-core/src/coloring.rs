@@ -0,0 +1,271 @@
+//! Ways of picking where in its gradient each pixel of a curve falls.
+//!
+//! Mappings are written as a name with an optional parameter, like
+//! `"periodic:5000"`:
+//!
+//! - `log2`: the fractional part of `log2(t + 1)`, restarting the gradient at
+//!   each power of two (the default)
+//! - `linear:<length>`: `t / length`, holding the last color after that
+//! - `periodic:<period>`: `t / period`, restarting every `period` pixels
+//! - `heading`: the direction the curve is going, once around the gradient
+//!   per full turn
+//! - `fold-depth:<levels>`: how deep in the folding the next corner is, the
+//!   number of trailing zeros of its turn number, out of `levels`
+//! - `curve-index`: which curve of the scene this is
+//! - `distance:<radius>`: straight-line distance from the start, out of
+//!   `radius` pixels
+
+use std::f64::consts::TAU;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorMapping {
+    #[default]
+    Log2,
+    Linear {
+        length: f64,
+    },
+    Periodic {
+        period: f64,
+    },
+    Heading,
+    FoldDepth {
+        levels: u32,
+    },
+    CurveIndex,
+    Distance {
+        radius: f64,
+    },
+}
+
+/// Names accepted when parsing a [`ColorMapping`].
+pub const COLOR_MAPPING_NAMES: [&str; 7] = [
+    "log2",
+    "linear",
+    "periodic",
+    "heading",
+    "fold-depth",
+    "curve-index",
+    "distance",
+];
+
+/// What a mapping gets to know about a pixel.
+#[derive(Clone, Copy, Debug)]
+pub struct Sample {
+    /// Number of pixels into the curve.
+    pub t: usize,
+    /// Number of segments finished before this pixel.
+    pub segment: u64,
+    /// Position relative to the start of the curve.
+    pub offset: (f64, f64),
+    /// Unit vector the curve is heading along.
+    pub direction: (f64, f64),
+    /// Index of the curve and number of curves in the scene.
+    pub curve: (usize, usize),
+}
+
+impl ColorMapping {
+    /// Depth into the gradient, in `0.0..=1.0`.
+    pub fn depth(&self, sample: &Sample) -> f64 {
+        let t = sample.t as f64;
+        match *self {
+            ColorMapping::Log2 => {
+                let d = (t + 1.0).log2();
+                d - d.floor()
+            }
+            ColorMapping::Linear { length } => (t / length).min(1.0),
+            ColorMapping::Periodic { period } => (t / period).fract(),
+            ColorMapping::Heading => {
+                let (dx, dy) = sample.direction;
+                (dy.atan2(dx) / TAU).rem_euclid(1.0)
+            }
+            ColorMapping::FoldDepth { levels } => {
+                let depth = (sample.segment + 1).trailing_zeros().min(levels);
+                depth as f64 / levels as f64
+            }
+            ColorMapping::CurveIndex => {
+                let (index, count) = sample.curve;
+                index as f64 / count.max(1) as f64
+            }
+            ColorMapping::Distance { radius } => {
+                (sample.offset.0.hypot(sample.offset.1) / radius).min(1.0)
+            }
+        }
+    }
+}
+
+impl FromStr for ColorMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ColorMapping, String> {
+        let (name, parameter) = match s.split_once(':') {
+            Some((name, parameter)) => (name.trim(), Some(parameter.trim())),
+            None => (s.trim(), None),
+        };
+        let positive = |default: f64| -> Result<f64, String> {
+            let Some(parameter) = parameter else {
+                return Ok(default);
+            };
+            match parameter.parse::<f64>() {
+                Ok(value) if value > 0.0 => Ok(value),
+                _ => Err(format!(
+                    "`{name}` needs a positive number, got `{parameter}`"
+                )),
+            }
+        };
+        let mapping = match name {
+            "log2" => ColorMapping::Log2,
+            "linear" => ColorMapping::Linear {
+                length: positive(1_000_000.0)?,
+            },
+            "periodic" => ColorMapping::Periodic {
+                period: positive(10_000.0)?,
+            },
+            "heading" => ColorMapping::Heading,
+            "fold-depth" => ColorMapping::FoldDepth {
+                levels: positive(16.0)?.round().max(1.0) as u32,
+            },
+            "curve-index" => ColorMapping::CurveIndex,
+            "distance" => ColorMapping::Distance {
+                radius: positive(500.0)?,
+            },
+            _ => {
+                return Err(format!(
+                    "unknown color mapping `{name}`, expected one of: {}",
+                    COLOR_MAPPING_NAMES.join(", ")
+                ))
+            }
+        };
+        let takes_parameter = matches!(
+            mapping,
+            ColorMapping::Linear { .. }
+                | ColorMapping::Periodic { .. }
+                | ColorMapping::FoldDepth { .. }
+                | ColorMapping::Distance { .. }
+        );
+        if parameter.is_some() && !takes_parameter {
+            return Err(format!("`{name}` doesn't take a parameter"));
+        }
+        Ok(mapping)
+    }
+}
+
+impl fmt::Display for ColorMapping {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorMapping::Log2 => write!(f, "log2"),
+            ColorMapping::Linear { length } => write!(f, "linear:{length}"),
+            ColorMapping::Periodic { period } => write!(f, "periodic:{period}"),
+            ColorMapping::Heading => write!(f, "heading"),
+            ColorMapping::FoldDepth { levels } => write!(f, "fold-depth:{levels}"),
+            ColorMapping::CurveIndex => write!(f, "curve-index"),
+            ColorMapping::Distance { radius } => write!(f, "distance:{radius}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(t: usize) -> Sample {
+        Sample {
+            t,
+            segment: 0,
+            offset: (0.0, 0.0),
+            direction: (1.0, 0.0),
+            curve: (0, 1),
+        }
+    }
+
+    fn depth(mapping: &str, sample: Sample) -> f64 {
+        mapping.parse::<ColorMapping>().unwrap().depth(&sample)
+    }
+
+    #[test]
+    fn maps_time() {
+        assert_eq!(depth("log2", sample(0)), 0.0);
+        assert_eq!(depth("log2", sample(2)), 3f64.log2() - 1.0);
+        assert_eq!(depth("log2", sample(7)), 0.0);
+        assert_eq!(depth("linear:100", sample(25)), 0.25);
+        assert_eq!(depth("linear:100", sample(250)), 1.0);
+        assert_eq!(depth("periodic:100", sample(250)), 0.5);
+    }
+
+    #[test]
+    fn maps_heading() {
+        let heading = |direction| {
+            depth(
+                "heading",
+                Sample {
+                    direction,
+                    ..sample(0)
+                },
+            )
+        };
+        assert_eq!(heading((1.0, 0.0)), 0.0);
+        assert_eq!(heading((0.0, 1.0)), 0.25);
+        assert_eq!(heading((-1.0, 0.0)), 0.5);
+        assert_eq!(heading((0.0, -1.0)), 0.75);
+    }
+
+    #[test]
+    fn maps_fold_depth() {
+        let fold_depth = |segment| {
+            depth(
+                "fold-depth:4",
+                Sample {
+                    segment,
+                    ..sample(0)
+                },
+            )
+        };
+        // the corner after segment n is turn n + 1
+        assert_eq!(fold_depth(0), 0.0);
+        assert_eq!(fold_depth(1), 0.25);
+        assert_eq!(fold_depth(7), 0.75);
+        assert_eq!(fold_depth(63), 1.0);
+    }
+
+    #[test]
+    fn maps_curve_index_and_distance() {
+        let index = Sample {
+            curve: (3, 4),
+            ..sample(0)
+        };
+        assert_eq!(depth("curve-index", index), 0.75);
+        let offset = Sample {
+            offset: (30.0, -40.0),
+            ..sample(0)
+        };
+        assert_eq!(depth("distance:100", offset), 0.5);
+        assert_eq!(depth("distance:10", offset), 1.0);
+    }
+
+    #[test]
+    fn parses_and_displays_mappings() {
+        assert_eq!("log2".parse(), Ok(ColorMapping::Log2));
+        assert_eq!(
+            " periodic : 5000 ".parse(),
+            Ok(ColorMapping::Periodic { period: 5000.0 })
+        );
+        assert_eq!(
+            "fold-depth".parse(),
+            Ok(ColorMapping::FoldDepth { levels: 16 })
+        );
+        for mapping in ["linear:12.5", "fold-depth:3", "distance:40", "curve-index"] {
+            let parsed: ColorMapping = mapping.parse().unwrap();
+            assert_eq!(parsed.to_string(), mapping);
+        }
+        for bad in [
+            "rainbow",
+            "linear:0",
+            "periodic:-3",
+            "distance:far",
+            "heading:2",
+        ] {
+            assert!(bad.parse::<ColorMapping>().is_err(), "{bad}");
+        }
+    }
+}
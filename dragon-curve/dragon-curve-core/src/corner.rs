@@ -0,0 +1,183 @@
+//! Rounded corners: each turn of a curve cut off by a circular arc tangent
+//! to both segments, so that places where the curve touches itself show up
+//! as separate paths instead of merging into one.
+
+/// A turn between two segments, rounded off by an arc.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Corner {
+    pub point: (f64, f64),    // where the two segments meet
+    pub incoming: (f64, f64), // unit direction of the segment before
+    pub outgoing: (f64, f64), // unit direction of the segment after
+    pub radius: f64,          // of the arc, shrunk if the segments are short
+    pub tangent: f64,         // distance from `point` to where the arc starts and ends
+    angle: f64,               // radians turned, clockwise on screen
+}
+
+impl Corner {
+    /// Rounds off the turn at `point` with an arc of `radius`, shrinking it
+    /// so the arc takes at most `room` off each segment. Returns `None` for
+    /// no radius, or where the curve goes straight on or turns right back.
+    pub fn new(
+        point: (f64, f64),
+        incoming: (f64, f64),
+        outgoing: (f64, f64),
+        radius: f64,
+        room: f64,
+    ) -> Option<Corner> {
+        let cross = incoming.0 * outgoing.1 - incoming.1 * outgoing.0;
+        let dot = incoming.0 * outgoing.0 + incoming.1 * outgoing.1;
+        if radius <= 0.0 || cross.abs() < 1e-9 {
+            return None;
+        }
+        let angle = cross.atan2(dot);
+        let slope = (angle.abs() / 2.0).tan();
+        let tangent = (radius * slope).min(room);
+        Some(Corner {
+            point,
+            incoming,
+            outgoing,
+            radius: tangent / slope,
+            tangent,
+            angle,
+        })
+    }
+
+    /// Whether the arc goes clockwise on screen.
+    pub fn clockwise(&self) -> bool {
+        self.angle > 0.0
+    }
+
+    /// The point `offset` pixels past the corner along the rounded path,
+    /// negative for points before it. The arc takes the place of the
+    /// `tangent` pixels either side of the corner, evenly spread over it.
+    pub fn at(&self, offset: f64) -> (f64, f64) {
+        let (point, t) = (self.point, self.tangent);
+        if offset <= -t {
+            return along(point, self.incoming, offset);
+        }
+        if offset >= t {
+            return along(point, self.outgoing, offset);
+        }
+        let start = along(point, self.incoming, -t);
+        let normal = if self.clockwise() {
+            (-self.incoming.1, self.incoming.0)
+        } else {
+            (self.incoming.1, -self.incoming.0)
+        };
+        let center = along(start, normal, self.radius);
+        let (x, y) = (start.0 - center.0, start.1 - center.1);
+        let (sin, cos) = (self.angle * (offset + t) / (2.0 * t)).sin_cos();
+        (center.0 + x * cos - y * sin, center.1 + x * sin + y * cos)
+    }
+}
+
+/// The point `distance` pixels along the straight run from `start` to `end`,
+/// cutting across the rounded corners at either end, if any.
+pub fn rounded_point(
+    start: (f64, f64),
+    end: (f64, f64),
+    corners: [Option<Corner>; 2],
+    distance: f64,
+) -> (f64, f64) {
+    let length = (end.0 - start.0).hypot(end.1 - start.1);
+    match corners {
+        [Some(corner), _] if distance < corner.tangent => corner.at(distance),
+        [_, Some(corner)] if distance > length - corner.tangent => corner.at(distance - length),
+        _ if length == 0.0 => start,
+        _ => {
+            let direction = ((end.0 - start.0) / length, (end.1 - start.1) / length);
+            along(start, direction, distance)
+        }
+    }
+}
+
+pub(crate) fn along(point: (f64, f64), direction: (f64, f64), distance: f64) -> (f64, f64) {
+    (
+        point.0 + direction.0 * distance,
+        point.1 + direction.1 * distance,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::SQRT_2;
+
+    fn assert_close(a: (f64, f64), b: (f64, f64)) {
+        assert!(
+            (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    /// Turning from heading right to heading down, clockwise on screen.
+    fn right_turn(radius: f64, room: f64) -> Corner {
+        Corner::new((10.0, 10.0), (1.0, 0.0), (0.0, 1.0), radius, room).unwrap()
+    }
+
+    #[test]
+    fn arcs_meet_the_segments_at_the_tangent_points() {
+        let corner = right_turn(2.0, 5.0);
+        assert!(corner.clockwise());
+        assert_close((corner.radius, corner.tangent), (2.0, 2.0));
+        assert_close(corner.at(-2.0), (8.0, 10.0));
+        assert_close(corner.at(2.0), (10.0, 12.0));
+        // straight on past them
+        assert_close(corner.at(-5.0), (5.0, 10.0));
+        assert_close(corner.at(3.0), (10.0, 13.0));
+    }
+
+    #[test]
+    fn arcs_keep_their_distance_from_the_center() {
+        let corner = right_turn(2.0, 5.0);
+        assert_close(corner.at(0.0), (8.0 + SQRT_2, 12.0 - SQRT_2));
+        for i in -10..=10 {
+            let (x, y) = corner.at(i as f64 / 5.0);
+            assert!(((x - 8.0).hypot(y - 12.0) - 2.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn arcs_turn_either_way() {
+        let corner = Corner::new((10.0, 10.0), (1.0, 0.0), (0.0, -1.0), 2.0, 5.0).unwrap();
+        assert!(!corner.clockwise());
+        assert_close(corner.at(0.0), (8.0 + SQRT_2, 8.0 + SQRT_2));
+        assert_close(corner.at(2.0), (10.0, 8.0));
+    }
+
+    #[test]
+    fn arcs_shrink_to_the_room_there_is() {
+        let corner = right_turn(2.0, 0.5);
+        assert_close((corner.radius, corner.tangent), (0.5, 0.5));
+        assert_close(corner.at(0.5), (10.0, 10.5));
+
+        // a 60 degree turn cuts off less of each segment
+        let (sin, cos) = 60f64.to_radians().sin_cos();
+        let corner = Corner::new((0.0, 0.0), (1.0, 0.0), (cos, sin), 3.0, 5.0).unwrap();
+        assert!((corner.tangent - 3.0 * 30f64.to_radians().tan()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn nothing_to_round() {
+        let (point, right) = ((0.0, 0.0), (1.0, 0.0));
+        assert!(Corner::new(point, right, (0.0, 1.0), 0.0, 5.0).is_none());
+        assert!(Corner::new(point, right, right, 2.0, 5.0).is_none());
+        assert!(Corner::new(point, right, (-1.0, 0.0), 2.0, 5.0).is_none());
+    }
+
+    #[test]
+    fn runs_cut_across_their_corners() {
+        let (start, end) = ((0.0, 10.0), (10.0, 10.0));
+        let corner = right_turn(2.0, 5.0);
+        assert_close(rounded_point(start, end, [None, None], 4.0), (4.0, 10.0));
+        assert_close(
+            rounded_point(start, end, [None, Some(corner)], 7.0),
+            (7.0, 10.0),
+        );
+        assert_close(
+            rounded_point(start, end, [None, Some(corner)], 10.0),
+            corner.at(0.0),
+        );
+        assert_close(rounded_point(start, start, [None, None], 0.0), start);
+    }
+}
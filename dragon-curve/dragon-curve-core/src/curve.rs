@@ -0,0 +1,956 @@
+use crate::bounds::{dragon_extent, Bounds};
+use crate::color::Color;
+use crate::coloring::{ColorMapping, Sample};
+use crate::corner::{along, rounded_point, Corner};
+use crate::framebuffer::{Blend, Framebuffer};
+use crate::gradient::Gradient;
+use crate::iter::{Pixels, Segment, Segments, TurnSequence};
+use crate::lsystem::{LSystem, LSystemPath};
+use crate::paperfolding::Folds;
+use crate::stroke::{Span, Stroke};
+use crate::turtle::Turtle;
+use std::rc::Rc;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    pub segment_length: usize,
+}
+
+#[derive(Clone)]
+pub struct State {
+    turtle: Turtle,
+    starting_direction: (isize, isize),
+    starting_position: (isize, isize),
+    angle: f64,              // degrees turned at each corner
+    segment_progress: usize, // number of pixels into a segment
+    t: usize,                // number of pixels into the curve
+    segment: u64,            // number of segments finished
+    gradient: Gradient,
+    coloring: ColorMapping,
+    curve: (usize, usize), // index of the curve and number of curves
+    blend: Blend,
+    opacity: f64,
+    stroke: Stroke,
+    corner_radius: f64,           // 0 for sharp corners
+    corners: [Option<Corner>; 2], // rounding off either end of the current segment
+    countdown: usize,             // number of iterations to wait before starting
+    duration: usize,              // number of pixels to draw before resetting, 0 for never
+    length: usize,                // number of pixels to draw before finishing, 0 for never
+    path: Path,
+}
+
+/// What decides where the curve goes next.
+#[derive(Clone)]
+enum Path {
+    /// Turns after every segment, the same way each time the curve restarts.
+    Turns(TurnSequence),
+    LSystem(LSystemPath),
+}
+
+impl State {
+    pub fn new(
+        starting_position: (isize, isize),
+        starting_direction: (isize, isize),
+        gradient: Gradient,
+        countdown: usize,
+        duration: usize,
+    ) -> State {
+        State {
+            turtle: Turtle::new(starting_position, starting_direction, 90.0),
+            starting_position,
+            starting_direction,
+            angle: 90.0,
+            segment_progress: 0,
+            t: 0,
+            segment: 0,
+            gradient,
+            coloring: ColorMapping::Log2,
+            curve: (0, 1),
+            blend: Blend::Replace,
+            opacity: 1.0,
+            stroke: Stroke::Pixels,
+            corner_radius: 0.0,
+            corners: [None, None],
+            countdown,
+            duration,
+            length: 0,
+            path: Path::Turns(TurnSequence::dragon()),
+        }
+    }
+
+    /// Picks gradient colors by `coloring` instead of `log2(t + 1)`.
+    pub fn with_coloring(mut self, coloring: ColorMapping) -> State {
+        self.coloring = coloring;
+        self
+    }
+
+    /// Tells the curve it's the `index`-th of `count`, for
+    /// [`ColorMapping::CurveIndex`].
+    pub fn with_curve_index(mut self, index: usize, count: usize) -> State {
+        self.curve = (index, count);
+        self
+    }
+
+    /// Mixes pixels into what's already drawn by `blend` instead of
+    /// overwriting them.
+    pub fn with_blend(mut self, blend: Blend) -> State {
+        self.blend = blend;
+        self
+    }
+
+    /// How strongly pixels are mixed in, from 0 (not at all) to 1.
+    pub fn with_opacity(mut self, opacity: f64) -> State {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Draws steps with `stroke` instead of one pixel each.
+    pub fn with_stroke(mut self, stroke: Stroke) -> State {
+        self.stroke = stroke;
+        self
+    }
+
+    /// Rounds off every turn with a quarter circle (or a wider or narrower
+    /// arc off the right-angle grid) of `radius` pixels, shrunk to fit
+    /// short segments. L-systems always turn sharply.
+    pub fn with_corner_radius(mut self, radius: f64) -> State {
+        self.corner_radius = radius;
+        self
+    }
+
+    /// Waits `countdown` steps before starting.
+    pub fn with_countdown(mut self, countdown: usize) -> State {
+        self.countdown = countdown;
+        self
+    }
+
+    /// Finishes for good after `length` pixels instead of going on forever.
+    /// A curve with a `duration` restarts before it can get that far.
+    pub fn with_length(mut self, length: usize) -> State {
+        self.length = length;
+        self
+    }
+
+    /// Turns by `angle` degrees at each corner instead of 90.
+    pub fn with_angle(mut self, angle: f64) -> State {
+        self.angle = angle;
+        self.turtle = Turtle::new(self.starting_position, self.starting_direction, angle);
+        self
+    }
+
+    /// Folds the strip following `folds` instead of the same way every time.
+    pub fn with_folds(mut self, folds: Folds) -> State {
+        self.path = Path::Turns(TurnSequence::paperfolding(folds));
+        self
+    }
+
+    /// Draws the terdragon, turning by 120 degrees unless told otherwise.
+    pub fn with_terdragon(mut self) -> State {
+        self.path = Path::Turns(TurnSequence::terdragon());
+        self.with_angle(120.0)
+    }
+
+    /// Follows an L-system instead of the built-in dragon turns, turning by
+    /// the L-system's angle.
+    pub fn with_lsystem(mut self, system: LSystem) -> State {
+        let angle = system.angle;
+        self.path = Path::LSystem(LSystemPath::new(Rc::new(system)));
+        self.with_angle(angle)
+    }
+
+    pub fn position(&self) -> (isize, isize) {
+        self.turtle.position()
+    }
+
+    /// Unit vector the curve is heading along.
+    pub fn direction(&self) -> (f64, f64) {
+        self.turtle.direction()
+    }
+
+    /// Number of pixels drawn since the curve last (re)started.
+    pub fn t(&self) -> usize {
+        self.t
+    }
+
+    pub fn gradient(&self) -> &Gradient {
+        &self.gradient
+    }
+
+    /// Colors the rest of the curve with `gradient`.
+    pub fn set_gradient(&mut self, gradient: Gradient) {
+        self.gradient = gradient;
+    }
+
+    pub fn coloring(&self) -> ColorMapping {
+        self.coloring
+    }
+
+    pub fn blend(&self) -> Blend {
+        self.blend
+    }
+
+    pub fn opacity(&self) -> f64 {
+        self.opacity
+    }
+
+    pub fn stroke(&self) -> Stroke {
+        self.stroke
+    }
+
+    pub fn corner_radius(&self) -> f64 {
+        self.corner_radius
+    }
+
+    /// Color of a pixel `progress` pixels further along the current segment.
+    fn color(&self, progress: usize) -> Color {
+        let (x, y) = self.turtle.point();
+        let direction = self.turtle.direction();
+        let sample = Sample {
+            t: self.t + progress,
+            segment: self.segment,
+            offset: (
+                x + direction.0 * progress as f64 - self.starting_position.0 as f64,
+                y + direction.1 * progress as f64 - self.starting_position.1 as f64,
+            ),
+            direction,
+            curve: self.curve,
+        };
+        self.gradient.color_at(self.coloring.depth(&sample))
+    }
+
+    /// The turns the curve makes from its start, or `None` for an L-system,
+    /// whose turns come from its commands.
+    pub fn turns(&self) -> Option<TurnSequence> {
+        match &self.path {
+            Path::Turns(turns) => {
+                let mut turns = turns.clone();
+                turns.seek(0);
+                Some(turns)
+            }
+            Path::LSystem(_) => None,
+        }
+    }
+
+    /// Whether the curve has nothing left to draw, which only happens once it
+    /// has drawn its `length`, or to an L-system that has run out of commands
+    /// and doesn't restart.
+    pub fn finished(&self) -> bool {
+        if self.reached_length() {
+            return true;
+        }
+        match &self.path {
+            Path::Turns(_) => false,
+            Path::LSystem(path) => self.duration == 0 && path.finished(),
+        }
+    }
+
+    /// Bounding box of the pixels the next `steps` steps draw, or `None` if
+    /// they draw nothing. For a dragon curve that hasn't started yet it's
+    /// worked out without stepping, and may reach up to a segment past the
+    /// last pixel; anything else is stepped through on a copy.
+    pub fn bounds(&self, config: &Config, steps: usize) -> Option<Bounds> {
+        let fresh = self.t == 0 && self.segment_progress == 0;
+        let dragon =
+            matches!(&self.path, Path::Turns(turns) if turns.folds() == Some(Folds::dragon()));
+        if !(fresh && dragon && self.angle == 90.0) {
+            let mut state = self.clone();
+            return (0..steps)
+                .filter_map(|_| state.step(config))
+                .map(|(position, _)| Bounds::point((position.0 as f64, position.1 as f64)))
+                .reduce(Bounds::union);
+        }
+
+        let steps = steps.saturating_sub(self.countdown);
+        let mut run = steps; // number of pixels before restarting or finishing
+        if self.duration > 0 {
+            run = run.min(self.duration + 1);
+        }
+        if self.length > 0 {
+            run = run.min(self.length);
+        }
+        if run == 0 {
+            return None;
+        }
+
+        let scale = config.segment_length as f64;
+        let (x, y) = (
+            self.starting_position.0 as f64,
+            self.starting_position.1 as f64,
+        );
+        let (dx, dy) = (
+            self.starting_direction.0 as f64,
+            self.starting_direction.1 as f64,
+        );
+        // box of a run of `pixels` pixels, with `flip` turning it around to
+        // face the other way as every restart does
+        let run_bounds = |pixels: usize, flip: f64| {
+            let last = (pixels - 1) as u64;
+            let segments = last.div_ceil(config.segment_length as u64);
+            // the extent heads along (1, 0), and turning right takes that to (0, 1)
+            dragon_extent(segments).map(|(a, b)| {
+                (
+                    x + flip * scale * (a * dx - b * dy),
+                    y + flip * scale * (a * dy + b * dx),
+                )
+            })
+        };
+        let bounds = run_bounds(run, 1.0);
+        let restarts =
+            self.duration > 0 && (self.length == 0 || self.length > self.duration) && steps > run;
+        if restarts {
+            Some(bounds.union(run_bounds((steps - run).min(run), -1.0)))
+        } else {
+            Some(bounds)
+        }
+    }
+
+    fn reached_length(&self) -> bool {
+        self.length > 0 && self.t >= self.length
+    }
+
+    /// The segments the curve draws from here on.
+    pub fn segments(self, config: Config) -> Segments {
+        Segments {
+            state: self,
+            config,
+        }
+    }
+
+    /// The stretches the curve draws in its next `steps` steps, split where
+    /// it restarts: the state each starts from and how many pixels of it are
+    /// drawn, leaving out the countdown. Walking each state's
+    /// [`State::segments`] for that many pixels draws what stepping would.
+    pub fn runs(&self, steps: usize) -> Vec<(State, usize)> {
+        let mut state = self.clone();
+        let mut steps = steps.saturating_sub(state.countdown);
+        state.countdown = 0;
+        let mut runs = Vec::new();
+        while steps > 0 {
+            let mut pixels = steps;
+            if state.duration > 0 {
+                pixels = pixels.min((state.duration + 1).saturating_sub(state.t));
+            }
+            let finishes = state.length > 0 && state.length - state.t.min(state.length) <= pixels;
+            if state.length > 0 {
+                pixels = pixels.min(state.length.saturating_sub(state.t));
+            }
+            if pixels > 0 {
+                runs.push((state.clone(), pixels));
+            }
+            steps -= pixels;
+            if finishes {
+                break;
+            }
+            if steps > 0 {
+                // as `step` restarts once the run is over, which takes no step
+                state.start_over();
+            }
+        }
+        runs
+    }
+
+    /// The pixels the curve draws from here on, with their colors.
+    pub fn pixels(self, config: Config) -> Pixels {
+        Pixels {
+            state: self,
+            config,
+        }
+    }
+
+    /// Goes back to the start of the curve heading the other way, as it does
+    /// once it has drawn its duration.
+    fn start_over(&mut self) {
+        self.starting_direction = turn(turn(self.starting_direction, Turn::R), Turn::R);
+        self.restart();
+    }
+
+    /// Goes back to the start of the curve.
+    fn restart(&mut self) {
+        self.turtle = Turtle::new(self.starting_position, self.starting_direction, self.angle);
+        self.segment_progress = 0;
+        self.segment = 0;
+        self.t = 0;
+        self.corners = [None, None];
+        match &mut self.path {
+            Path::Turns(turns) => turns.seek(0),
+            Path::LSystem(path) => *path = LSystemPath::new(Rc::clone(path.system())),
+        }
+    }
+
+    /// Jumps to `t` pixels into the curve, as if it had been stepped `t` times
+    /// since it started, without drawing anything.
+    ///
+    /// Dragon and paperfolding curves get there in one step per bit of the
+    /// segment number using [`Folds::point_at`]; other curves are walked.
+    /// Off the right-angle grid the result can land a pixel away from where
+    /// walking would, since walking accumulates rounding error.
+    pub fn seek(&mut self, config: &Config, t: usize) {
+        self.countdown = 0;
+        self.restart();
+        let Some(folds) = self.turns().and_then(|turns| turns.folds()) else {
+            for _ in 0..t {
+                self.step(config);
+            }
+            return;
+        };
+
+        let segments = t / config.segment_length;
+        let progress = t % config.segment_length;
+        let (point, direction) = folds.point_at(segments as u64, self.angle);
+        // rotate from the origin heading along (1, 0) to the actual start
+        let (sx, sy) = (
+            self.starting_direction.0 as f64,
+            self.starting_direction.1 as f64,
+        );
+        let along = (
+            point.0 * config.segment_length as f64 + direction.0 * progress as f64,
+            point.1 * config.segment_length as f64 + direction.1 * progress as f64,
+        );
+        let position = (
+            self.starting_position.0 as f64 + along.0 * sx - along.1 * sy,
+            self.starting_position.1 as f64 + along.0 * sy + along.1 * sx,
+        );
+        let direction = (
+            direction.0 * sx - direction.1 * sy,
+            direction.0 * sy + direction.1 * sx,
+        );
+
+        self.turtle = Turtle::at(position, direction, self.angle);
+        self.t = t;
+        self.segment_progress = progress;
+        self.segment = segments as u64;
+        if let Path::Turns(turns) = &mut self.path {
+            turns.seek(segments as u64);
+        }
+        self.round_corners(config, true);
+    }
+
+    /// Advances the curve `steps` steps without drawing anything, as if
+    /// [`State::step`] had been called that many times. Dragon and
+    /// paperfolding curves that never restart jump straight there with
+    /// [`State::seek`]; the rest are walked.
+    pub fn skip(&mut self, config: &Config, steps: usize) {
+        let waiting = steps.min(self.countdown);
+        self.countdown -= waiting;
+        let steps = steps - waiting;
+        if steps == 0 {
+            return;
+        }
+        let seekable = self.turns().is_some_and(|turns| turns.folds().is_some());
+        if self.duration > 0 || !seekable {
+            for _ in 0..steps {
+                self.step(config);
+            }
+            return;
+        }
+        let t = self.t + steps;
+        let t = if self.length > 0 {
+            t.min(self.length)
+        } else {
+            t
+        };
+        self.seek(config, t);
+    }
+
+    /// Advances the curve by one pixel, returning the pixel that should be
+    /// drawn and its color, or `None` if nothing should be drawn, like while
+    /// the curve is still counting down.
+    pub fn step(&mut self, config: &Config) -> Option<((isize, isize), Color)> {
+        self.step_span(config)
+            .map(|(span, color)| (span.pixel(), color))
+    }
+
+    /// Advances the curve by one pixel like [`State::step`], returning the
+    /// exact stretch of curve the step covers instead of a single pixel.
+    pub fn step_span(&mut self, config: &Config) -> Option<(Span, Color)> {
+        if self.countdown > 0 {
+            self.countdown -= 1;
+            return None;
+        }
+        if self.reached_length() {
+            return None;
+        }
+
+        if self.duration > 0 && self.t > self.duration {
+            self.start_over();
+        }
+
+        let Some(drawing) = self.start_segment() else {
+            self.t += 1;
+            return None; // out of commands
+        };
+
+        let first = self.segment_progress == 0;
+        if first {
+            self.round_corners(config, false);
+        }
+        let progress = self.segment_progress as f64;
+        let start = self.turtle.point();
+        let color = self.color(0);
+
+        // update state
+        self.t += 1;
+        self.segment_progress += 1;
+
+        self.turtle.forward();
+        let last = self.segment_progress >= config.segment_length;
+        let span = match self.corners {
+            [None, None] => Span {
+                start,
+                end: self.turtle.point(),
+                first,
+                last,
+            },
+            corners => {
+                let length = config.segment_length as f64;
+                let direction = self.turtle.direction();
+                let segment_start = along(start, direction, -progress);
+                let segment_end = along(segment_start, direction, length);
+                let at = |distance| rounded_point(segment_start, segment_end, corners, distance);
+                // cap steps around the arcs, whose directions don't line up
+                let rounding = |distance: f64| match corners {
+                    [Some(corner), _] if distance < corner.tangent => true,
+                    [_, Some(corner)] => distance > length - corner.tangent,
+                    _ => false,
+                };
+                let arc = rounding(progress) || rounding(progress + 1.0);
+                Span {
+                    start: at(progress),
+                    end: at(progress + 1.0),
+                    first: first || arc,
+                    last: last || arc,
+                }
+            }
+        };
+        if last {
+            self.end_segment();
+        }
+
+        drawing.then_some((span, color))
+    }
+
+    /// Moves to the end of the current segment in one go, or to where the
+    /// curve finishes or restarts part way along it, ignoring the countdown.
+    /// Returns the segment and whether it's drawn, or `None` once the curve
+    /// finishes.
+    pub(crate) fn next_segment(&mut self, config: &Config) -> Option<(Segment, bool)> {
+        if self.reached_length() {
+            return None;
+        }
+        if self.duration > 0 && self.t > self.duration {
+            self.start_over();
+        }
+        let drawing = match self.start_segment() {
+            Some(drawing) => drawing,
+            // an L-system out of commands draws nothing until it restarts
+            None if self.duration > 0 => {
+                self.start_over();
+                self.start_segment()?
+            }
+            None => return None,
+        };
+        if self.segment_progress == 0 {
+            self.round_corners(config, false);
+        }
+        let corners = match self.segment_progress {
+            0 => self.corners,
+            _ => [None, self.corners[1]],
+        };
+        let start = self.turtle.point();
+        let t = self.t;
+        let remaining = config.segment_length - self.segment_progress;
+        let mut pixels = remaining;
+        if self.duration > 0 {
+            pixels = pixels.min(self.duration + 1 - t);
+        }
+        if self.length > 0 {
+            pixels = pixels.min(self.length - t);
+        }
+        let color = self.color(pixels / 2);
+        let end = {
+            let mut turtle = self.turtle.clone();
+            turtle.forward_by(remaining);
+            turtle.point()
+        };
+        self.turtle.forward_by(pixels);
+        self.t += pixels;
+        self.segment_progress += pixels;
+        if pixels == remaining {
+            self.end_segment();
+        }
+        Some((
+            Segment {
+                start,
+                end,
+                t,
+                pixels,
+                color,
+                corners,
+            },
+            drawing,
+        ))
+    }
+
+    /// Runs an L-system's commands up to the next segment if one is due.
+    /// Returns whether the segment is drawn, or `None` when out of commands.
+    fn start_segment(&mut self) -> Option<bool> {
+        match &mut self.path {
+            Path::Turns(_) => Some(true),
+            Path::LSystem(path) => {
+                if self.segment_progress == 0 && !path.start_segment(&mut self.turtle) {
+                    return None;
+                }
+                Some(path.drawing())
+            }
+        }
+    }
+
+    /// Works out the rounded corner at the end of the current segment, and
+    /// at its start too if `both`; otherwise that's the one carried over from
+    /// the end of the previous segment.
+    fn round_corners(&mut self, config: &Config, both: bool) {
+        let Path::Turns(turns) = &self.path else {
+            return;
+        };
+        if self.corner_radius <= 0.0 {
+            return;
+        }
+        let length = config.segment_length as f64;
+        let direction = self.turtle.direction();
+        let start = along(
+            self.turtle.point(),
+            direction,
+            -(self.segment_progress as f64),
+        );
+        let heading = |turn: Turn| {
+            let mut turtle = self.turtle.clone();
+            turtle.turn(turn);
+            turtle.direction()
+        };
+        let corner = |point, incoming, outgoing| {
+            Corner::new(point, incoming, outgoing, self.corner_radius, length / 2.0)
+        };
+        if both {
+            self.corners[0] = turns
+                .previous()
+                .and_then(|previous| corner(start, heading(previous.opposite()), direction));
+        }
+        let end = along(start, direction, length);
+        self.corners[1] = corner(end, direction, heading(turns.peek()));
+    }
+
+    fn end_segment(&mut self) {
+        // the L-system turns at the start of a segment instead
+        if let Path::Turns(turns) = &mut self.path {
+            if let Some(current_turn) = turns.next() {
+                self.turtle.turn(current_turn);
+            }
+        }
+        self.segment_progress = 0;
+        self.segment += 1;
+        self.corners = [self.corners[1], None];
+    }
+}
+
+/// The turn after segment `n - 1` of the terdragon, for `n >= 1`: dropping
+/// factors of 3 from `n` leaves a remainder of 1 for a left turn and 2 for a
+/// right turn.
+///
+/// # Panics
+///
+/// If `n` is 0, which has no turn before it and can't have its factors of 3
+/// dropped.
+pub fn terdragon_turn(mut n: u64) -> Turn {
+    assert!(n > 0, "the terdragon's turns start at n = 1");
+    while n.is_multiple_of(3) {
+        n /= 3;
+    }
+    if n % 3 == 1 {
+        Turn::L
+    } else {
+        Turn::R
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Turn {
+    L,
+    R,
+}
+
+impl Turn {
+    pub fn opposite(self) -> Turn {
+        match self {
+            Turn::L => Turn::R,
+            Turn::R => Turn::L,
+        }
+    }
+}
+
+pub fn turn(direction: (isize, isize), turn: Turn) -> (isize, isize) {
+    match turn {
+        Turn::L => (direction.1, -direction.0),
+        Turn::R => (-direction.1, direction.0),
+    }
+}
+
+/// Advances `state` by one pixel and draws that step into `framebuffer` with
+/// the curve's stroke.
+pub fn update(config: &Config, framebuffer: &mut impl Framebuffer, state: &mut State) {
+    if let Some((span, color)) = state.step_span(config) {
+        state
+            .stroke
+            .draw(framebuffer, &span, color, state.blend, state.opacity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradient::{hsv_gradient, Gradient};
+
+    fn state(countdown: usize, duration: usize) -> State {
+        State::new(
+            (0, 0),
+            (1, 0),
+            Gradient::new(hsv_gradient()).unwrap(),
+            countdown,
+            duration,
+        )
+    }
+
+    fn positions(state: &mut State, config: &Config, n: usize) -> Vec<Option<(isize, isize)>> {
+        (0..n)
+            .map(|_| state.step(config).map(|(position, _)| position))
+            .collect()
+    }
+
+    fn steps(state: &mut State, config: &Config, n: usize) -> Vec<Option<((isize, isize), Color)>> {
+        (0..n).map(|_| state.step(config)).collect()
+    }
+
+    #[test]
+    fn follows_the_dragon_turns() {
+        // the first turns of the regular paperfolding sequence
+        let turns = "RRLRRLLRRRLLRLL";
+        let (mut position, mut direction) = ((0, 0), (1, 0));
+        let mut expected = vec![Some(position)];
+        for c in turns.chars() {
+            position = (position.0 + direction.0, position.1 + direction.1);
+            direction = turn(direction, if c == 'L' { Turn::L } else { Turn::R });
+            expected.push(Some(position));
+        }
+        let config = Config { segment_length: 1 };
+        assert_eq!(
+            positions(&mut state(0, 0), &config, expected.len()),
+            expected
+        );
+    }
+
+    #[test]
+    fn follows_fold_patterns() {
+        let config = Config { segment_length: 1 };
+        let dragon = positions(&mut state(0, 0), &config, 64);
+        let mut folded = state(0, 0).with_folds(Folds::dragon());
+        assert_eq!(positions(&mut folded, &config, 64), dragon);
+
+        // folding the other way every time mirrors the dragon
+        let mut mirrored = state(0, 0).with_folds("L".parse().unwrap());
+        let mirror = dragon.iter().map(|p| p.map(|(x, y)| (x, -y)));
+        assert!(positions(&mut mirrored, &config, 64).into_iter().eq(mirror));
+    }
+
+    #[test]
+    fn terdragon_turns() {
+        let turns: String = (1..=9)
+            .map(|n| format!("{:?}", terdragon_turn(n)))
+            .collect();
+        assert_eq!(turns, "LRLLRRLRL");
+    }
+
+    #[test]
+    #[should_panic(expected = "start at n = 1")]
+    fn terdragon_turns_start_at_one() {
+        terdragon_turn(0);
+    }
+
+    #[test]
+    fn turns_by_the_angle() {
+        let config = Config { segment_length: 1 };
+        let dragon = positions(&mut state(0, 0), &config, 64);
+        let mut square = state(0, 0).with_angle(90.0);
+        assert_eq!(positions(&mut square, &config, 64), dragon);
+
+        let mut terdragon = state(0, 0).with_terdragon();
+        terdragon.step(&config);
+        let (x, y) = terdragon.direction();
+        // the first turn is 120 degrees to the left
+        assert!((x + 0.5).abs() < 1e-9 && (y + 0.75f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn turns_once_per_segment() {
+        let config = Config { segment_length: 3 };
+        let expected = [(0, 0), (1, 0), (2, 0), (3, 0), (3, 1), (3, 2), (3, 3)].map(Some);
+        assert_eq!(positions(&mut state(0, 0), &config, 7), expected);
+    }
+
+    #[test]
+    fn counts_down_before_starting() {
+        let config = Config { segment_length: 1 };
+        let mut state = state(2, 0);
+        assert_eq!(
+            positions(&mut state, &config, 3),
+            [None, None, Some((0, 0))]
+        );
+        assert_eq!(state.t(), 1);
+    }
+
+    #[test]
+    fn restarts_the_other_way_after_the_duration() {
+        let config = Config { segment_length: 1 };
+        let mut state = state(0, 4);
+        positions(&mut state, &config, 5);
+        assert_eq!(
+            positions(&mut state, &config, 2),
+            [Some((0, 0)), Some((-1, 0))]
+        );
+        assert_eq!(state.t(), 2);
+    }
+
+    #[test]
+    fn colors_by_the_fraction_of_the_doubling() {
+        let config = Config { segment_length: 1 };
+        let mut state = state(0, 0);
+        let colors: Vec<Color> = (0..4).map(|_| state.step(&config).unwrap().1).collect();
+        // steps 1 and 2 are whole powers of two, back at the start of the gradient
+        assert_eq!(
+            colors[0],
+            Gradient::new(hsv_gradient()).unwrap().color_at(0.0)
+        );
+        assert_eq!(colors[1], colors[0]);
+        assert_eq!(
+            colors[2],
+            Gradient::new(hsv_gradient())
+                .unwrap()
+                .color_at(3f64.log2() - 1.0)
+        );
+        assert_eq!(colors[3], colors[0]);
+    }
+
+    #[test]
+    fn seek_matches_stepping() {
+        fn upward() -> State {
+            State::new(
+                (40, 30),
+                (0, -1),
+                Gradient::new(hsv_gradient()).unwrap(),
+                0,
+                0,
+            )
+        }
+        fn folded() -> State {
+            upward().with_folds("RLL".parse().unwrap())
+        }
+        for (state, segment_length) in [(upward as fn() -> State, 1), (upward, 3), (folded, 2)] {
+            let config = Config { segment_length };
+            for t in [0, 1, 2, 5, 64, 100, 1000] {
+                let mut stepped = state();
+                steps(&mut stepped, &config, t);
+                let mut sought = state();
+                sought.seek(&config, t);
+                assert_eq!(sought.t(), t);
+                assert_eq!(sought.position(), stepped.position(), "t = {t}");
+                assert_eq!(
+                    steps(&mut sought, &config, 20),
+                    steps(&mut stepped, &config, 20),
+                    "t = {t}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn skip_matches_stepping() {
+        let config = Config { segment_length: 2 };
+        for state in [
+            state(0, 0).with_countdown(7),
+            state(0, 0).with_length(50),
+            state(3, 40),
+            state(0, 0).with_terdragon(),
+            state(0, 0).with_corner_radius(0.5),
+        ] {
+            for n in [0, 3, 10, 49, 50, 120] {
+                let mut stepped = state.clone();
+                steps(&mut stepped, &config, n);
+                let mut skipped = state.clone();
+                skipped.skip(&config, n);
+                assert_eq!(skipped.t(), stepped.t(), "n = {n}");
+                assert_eq!(
+                    steps(&mut skipped, &config, 20),
+                    steps(&mut stepped, &config, 20),
+                    "n = {n}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn runs_draw_what_stepping_does() {
+        let config = Config { segment_length: 2 };
+        for state in [
+            state(0, 0),
+            state(3, 4),
+            state(0, 10).with_length(6),
+            state(0, 6).with_length(20),
+        ] {
+            for n in [0, 1, 5, 6, 30] {
+                let drawn: Vec<_> = steps(&mut state.clone(), &config, n)
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                let runs: Vec<_> = state
+                    .runs(n)
+                    .into_iter()
+                    .flat_map(|(run, pixels)| run.pixels(config).take(pixels))
+                    .collect();
+                assert_eq!(runs, drawn, "n = {n}");
+            }
+        }
+    }
+
+    #[test]
+    fn bounds_hold_the_stepped_path() {
+        for (state, segment_length) in [
+            (state(0, 0), 1),
+            (state(0, 0), 5),
+            (state(0, 0).with_countdown(30), 2),
+            (state(0, 0).with_length(77), 3),
+            (state(0, 90), 2),
+            (state(0, 0).with_folds("RLL".parse().unwrap()), 1),
+            (state(0, 0).with_terdragon(), 2),
+            (state(0, 0).with_corner_radius(0.5), 1),
+        ] {
+            let config = Config { segment_length };
+            for steps in [1, 2, 7, 64, 200, 1000] {
+                let mut stepped = state.clone();
+                let walked = (0..steps)
+                    .filter_map(|_| stepped.step(&config))
+                    .map(|(position, _)| Bounds::point((position.0 as f64, position.1 as f64)))
+                    .reduce(Bounds::union);
+                let bounds = state.bounds(&config, steps);
+                let (Some(walked), Some(bounds)) = (walked, bounds) else {
+                    assert!(walked.is_none() && bounds.is_none(), "{steps} steps");
+                    continue;
+                };
+                // worked out bounds may reach up to a segment past the end
+                let slack = segment_length as f64;
+                assert!(bounds.union(walked) == bounds, "{steps} steps");
+                assert!(walked.min.0 - bounds.min.0 <= slack, "{steps} steps");
+                assert!(walked.min.1 - bounds.min.1 <= slack, "{steps} steps");
+                assert!(bounds.max.0 - walked.max.0 <= slack, "{steps} steps");
+                assert!(bounds.max.1 - walked.max.1 <= slack, "{steps} steps");
+            }
+        }
+    }
+}
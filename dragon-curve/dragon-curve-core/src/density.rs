@@ -0,0 +1,245 @@
+//! Heatmap-style rendering: instead of each pixel keeping the color drawn
+//! last, every visit is counted and its color summed, and a tone-mapping pass
+//! turns the counts into brightness.
+//!
+//! Tone maps are written as a name with an optional parameter, like
+//! `"gamma:2.2"`:
+//!
+//! - `log`: `ln(1 + count)` against the busiest pixel (the default)
+//! - `gamma:<gamma>`: `count` against the busiest pixel, raised to
+//!   `1 / gamma`
+//! - `equalize`: the fraction of visited pixels with at most as many visits,
+//!   spreading brightness evenly however lopsided the counts are
+
+use crate::color::{Color, BLACK};
+use crate::curve::{Config, State};
+use crate::framebuffer::Framebuffer;
+use std::fmt;
+use std::str::FromStr;
+
+/// Per-pixel visit counts and summed colors.
+#[derive(Clone)]
+pub struct Density {
+    width: usize,
+    height: usize,
+    counts: Vec<u32>,
+    sums: Vec<Color>,
+}
+
+impl Density {
+    pub fn new(width: usize, height: usize) -> Density {
+        Density {
+            width,
+            height,
+            counts: vec![0; width * height],
+            sums: vec![BLACK; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Counts a visit, silently dropping it if it falls outside.
+    pub fn add(&mut self, position: (isize, isize), color: Color) {
+        let (Ok(x), Ok(y)) = (usize::try_from(position.0), usize::try_from(position.1)) else {
+            return;
+        };
+        if x < self.width && y < self.height {
+            let i = x + y * self.width;
+            self.counts[i] = self.counts[i].saturating_add(1);
+            let sum = &mut self.sums[i];
+            *sum = (sum.0 + color.0, sum.1 + color.1, sum.2 + color.2);
+        }
+    }
+
+    /// Forgets every visit.
+    pub fn clear(&mut self) {
+        self.counts.fill(0);
+        self.sums.fill(BLACK);
+    }
+
+    /// Draws every pixel into `framebuffer` as its average color, dimmed by
+    /// how its visit count tone-maps. Unvisited pixels are left alone.
+    pub fn tone_map(&self, tone_map: ToneMap, framebuffer: &mut impl Framebuffer) {
+        assert_eq!(
+            (framebuffer.width(), framebuffer.height()),
+            (self.width, self.height),
+            "framebuffer size mismatch"
+        );
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+        if max == 0 {
+            return;
+        }
+        // only needed for equalizing, sorted so ranks are a binary search away
+        let mut visited = Vec::new();
+        if tone_map == ToneMap::Equalize {
+            visited.extend(self.counts.iter().copied().filter(|&count| count > 0));
+            visited.sort_unstable();
+        }
+
+        for (i, (&count, &sum)) in self.counts.iter().zip(&self.sums).enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let brightness = match tone_map {
+                ToneMap::Log => (count as f64).ln_1p() / (max as f64).ln_1p(),
+                ToneMap::Gamma { gamma } => (count as f64 / max as f64).powf(1.0 / gamma),
+                ToneMap::Equalize => {
+                    visited.partition_point(|&c| c <= count) as f64 / visited.len() as f64
+                }
+            };
+            let scale = brightness / count as f64;
+            let color = (
+                (sum.0 * scale).min(1.0),
+                (sum.1 * scale).min(1.0),
+                (sum.2 * scale).min(1.0),
+            );
+            framebuffer.set_pixel(i % self.width, i / self.width, color);
+        }
+    }
+}
+
+/// Advances `state` by one pixel and counts that pixel in `density`.
+pub fn accumulate(config: &Config, density: &mut Density, state: &mut State) {
+    if let Some((position, color)) = state.step(config) {
+        density.add(position, color);
+    }
+}
+
+/// How visit counts become brightness.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ToneMap {
+    #[default]
+    Log,
+    Gamma {
+        gamma: f64,
+    },
+    Equalize,
+}
+
+/// Names accepted when parsing a [`ToneMap`].
+pub const TONE_MAP_NAMES: [&str; 3] = ["log", "gamma", "equalize"];
+
+impl FromStr for ToneMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ToneMap, String> {
+        let (name, parameter) = match s.split_once(':') {
+            Some((name, parameter)) => (name.trim(), Some(parameter.trim())),
+            None => (s.trim(), None),
+        };
+        let tone_map = match name {
+            "log" => ToneMap::Log,
+            "gamma" => {
+                let gamma = match parameter {
+                    None => 2.2,
+                    Some(parameter) => match parameter.parse::<f64>() {
+                        Ok(gamma) if gamma > 0.0 => gamma,
+                        _ => {
+                            return Err(format!(
+                                "`gamma` needs a positive number, got `{parameter}`"
+                            ))
+                        }
+                    },
+                };
+                ToneMap::Gamma { gamma }
+            }
+            "equalize" => ToneMap::Equalize,
+            _ => {
+                return Err(format!(
+                    "unknown tone map `{name}`, expected one of: {}",
+                    TONE_MAP_NAMES.join(", ")
+                ))
+            }
+        };
+        if parameter.is_some() && !matches!(tone_map, ToneMap::Gamma { .. }) {
+            return Err(format!("`{name}` doesn't take a parameter"));
+        }
+        Ok(tone_map)
+    }
+}
+
+impl fmt::Display for ToneMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ToneMap::Log => write!(f, "log"),
+            ToneMap::Gamma { gamma } => write!(f, "gamma:{gamma}"),
+            ToneMap::Equalize => write!(f, "equalize"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::WHITE;
+    use crate::framebuffer::RgbaFramebuffer;
+
+    /// Tone-maps one pixel visited once and one visited three times,
+    /// returning their brightness.
+    fn brightness(tone_map: ToneMap) -> (f64, f64) {
+        let mut density = Density::new(3, 1);
+        density.add((0, 0), WHITE);
+        for _ in 0..3 {
+            density.add((1, 0), WHITE);
+        }
+        density.add((5, 0), WHITE);
+        let mut frame = vec![0; 3 * 4];
+        let mut framebuffer = RgbaFramebuffer::new(&mut frame, 3, 1);
+        density.tone_map(tone_map, &mut framebuffer);
+        assert_eq!(framebuffer.get_pixel(2, 0), BLACK);
+        (framebuffer.get_pixel(0, 0).0, framebuffer.get_pixel(1, 0).0)
+    }
+
+    fn assert_close((a, b): (f64, f64), (c, d): (f64, f64)) {
+        // to within the rounding of an 8-bit channel
+        assert!((a - c).abs() < 1.0 / 255.0 && (b - d).abs() < 1.0 / 255.0);
+    }
+
+    #[test]
+    fn tone_maps_counts_against_the_busiest_pixel() {
+        assert_close(brightness(ToneMap::Log), (0.5, 1.0));
+        assert_close(brightness(ToneMap::Gamma { gamma: 1.0 }), (1.0 / 3.0, 1.0));
+        assert_close(
+            brightness(ToneMap::Gamma { gamma: 2.0 }),
+            (3f64.powf(-0.5), 1.0),
+        );
+        assert_close(brightness(ToneMap::Equalize), (0.5, 1.0));
+    }
+
+    #[test]
+    fn tone_maps_parse_and_display() {
+        for tone_map in [
+            ToneMap::Log,
+            ToneMap::Gamma { gamma: 2.2 },
+            ToneMap::Gamma { gamma: 0.5 },
+            ToneMap::Equalize,
+        ] {
+            assert_eq!(tone_map.to_string().parse::<ToneMap>(), Ok(tone_map));
+        }
+        assert_eq!(
+            "gamma".parse::<ToneMap>(),
+            Ok(ToneMap::Gamma { gamma: 2.2 })
+        );
+        assert_eq!(" log ".parse::<ToneMap>(), Ok(ToneMap::Log));
+    }
+
+    #[test]
+    fn rejects_bad_tone_maps() {
+        for bad in [
+            "heat",
+            "gamma:0",
+            "gamma:-1",
+            "gamma:NaN",
+            "gamma:x",
+            "log:2",
+        ] {
+            assert!(bad.parse::<ToneMap>().is_err(), "{bad}");
+        }
+    }
+}
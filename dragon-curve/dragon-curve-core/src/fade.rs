@@ -0,0 +1,100 @@
+//! Trails that fade toward the background, so scenes that keep restarting
+//! their curves show the most recent growth instead of slowly filling up
+//! with noise.
+
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// Smallest fading worth doing at once: 8-bit channels round away anything
+// much gentler, so bright pixels would never fade.
+const MAX_FACTOR: f64 = 0.95;
+
+#[derive(Clone, Debug)]
+pub struct Fade {
+    half_life: f64, // number of steps for a pixel to fade halfway
+    background: Color,
+    pending: usize, // number of steps since the framebuffer was last faded
+}
+
+impl Fade {
+    /// Fades pixels halfway to `background` every `half_life` steps.
+    pub fn new(half_life: f64, background: Color) -> Fade {
+        Fade {
+            half_life,
+            background,
+            pending: 0,
+        }
+    }
+
+    pub fn half_life(&self) -> f64 {
+        self.half_life
+    }
+
+    /// Forgets the steps counted since the framebuffer was last faded.
+    pub fn reset(&mut self) {
+        self.pending = 0;
+    }
+
+    /// Counts `steps` more steps, fading `framebuffer` once they add up to a
+    /// visible change.
+    pub fn advance(&mut self, steps: usize, framebuffer: &mut impl Framebuffer) {
+        self.pending += steps;
+        let factor = 0.5f64.powf(self.pending as f64 / self.half_life);
+        if factor <= MAX_FACTOR {
+            framebuffer.fade(self.background, 1.0 - factor);
+            self.pending = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{BLACK, WHITE};
+
+    /// A one-pixel framebuffer that keeps colors exactly.
+    struct Pixel(Color);
+
+    impl Framebuffer for Pixel {
+        fn width(&self) -> usize {
+            1
+        }
+
+        fn height(&self) -> usize {
+            1
+        }
+
+        fn set_pixel(&mut self, _: usize, _: usize, color: Color) {
+            self.0 = color;
+        }
+
+        fn get_pixel(&self, _: usize, _: usize) -> Color {
+            self.0
+        }
+    }
+
+    #[test]
+    fn fades_halfway_each_half_life() {
+        let mut pixel = Pixel(WHITE);
+        let mut fade = Fade::new(10.0, BLACK);
+        fade.advance(10, &mut pixel);
+        assert_eq!(pixel.0, (0.5, 0.5, 0.5));
+        fade.advance(20, &mut pixel);
+        assert_eq!(pixel.0, (0.125, 0.125, 0.125));
+    }
+
+    #[test]
+    fn saves_up_small_steps() {
+        let mut pixel = Pixel(WHITE);
+        let mut fade = Fade::new(100.0, (0.0, 0.0, 1.0));
+        // 0.5^(7 / 100) is just over MAX_FACTOR
+        for _ in 0..7 {
+            fade.advance(1, &mut pixel);
+        }
+        assert_eq!(pixel.0, WHITE);
+        fade.advance(1, &mut pixel);
+        let factor = 0.5f64.powf(0.08);
+        assert!((pixel.0 .0 - factor).abs() < 1e-12);
+        assert_eq!(pixel.0 .2, 1.0);
+    }
+}
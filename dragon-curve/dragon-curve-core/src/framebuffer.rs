@@ -0,0 +1,296 @@
+use crate::color::{from_bgra, from_rgba, lerp_color, to_bgra, to_rgba, Color};
+use std::fmt;
+use std::str::FromStr;
+
+/// Something the curves can be drawn into.
+pub trait Framebuffer {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+
+    /// Writes a pixel that is known to be inside the framebuffer.
+    fn set_pixel(&mut self, x: usize, y: usize, color: Color);
+
+    /// Reads a pixel that is known to be inside the framebuffer.
+    fn get_pixel(&self, x: usize, y: usize) -> Color;
+
+    /// Writes a pixel, silently dropping it if it falls outside the framebuffer.
+    fn plot(&mut self, position: (isize, isize), color: Color) {
+        if let Some((x, y)) = self.inside(position) {
+            self.set_pixel(x, y, color);
+        }
+    }
+
+    /// Mixes a pixel into what's already there, silently dropping it if it
+    /// falls outside the framebuffer.
+    fn blend(&mut self, position: (isize, isize), color: Color, blend: Blend, opacity: f64) {
+        if let Some((x, y)) = self.inside(position) {
+            let color = match blend {
+                Blend::Replace => color,
+                _ => blend.apply(self.get_pixel(x, y), color, opacity),
+            };
+            self.set_pixel(x, y, color);
+        }
+    }
+
+    /// Moves every pixel `amount` of the way toward `color`.
+    fn fade(&mut self, color: Color, amount: f64) {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let pixel = self.get_pixel(x, y);
+                if pixel != color {
+                    self.set_pixel(x, y, lerp_color(pixel, color, amount));
+                }
+            }
+        }
+    }
+
+    /// `position` as unsigned coordinates, or `None` if it's outside.
+    fn inside(&self, position: (isize, isize)) -> Option<(usize, usize)> {
+        let (x, y) = (
+            usize::try_from(position.0).ok()?,
+            usize::try_from(position.1).ok()?,
+        );
+        (x < self.width() && y < self.height()).then_some((x, y))
+    }
+
+    /// Fills the whole framebuffer with one color.
+    fn clear(&mut self, color: Color) {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                self.set_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+/// A `u32`-per-pixel buffer, as used by minifb.
+pub struct BgraFramebuffer<'a> {
+    buffer: &'a mut [u32],
+    width: usize,
+    height: usize,
+}
+
+impl<'a> BgraFramebuffer<'a> {
+    pub fn new(buffer: &'a mut [u32], width: usize, height: usize) -> BgraFramebuffer<'a> {
+        assert_eq!(buffer.len(), width * height, "buffer size mismatch");
+        BgraFramebuffer {
+            buffer,
+            width,
+            height,
+        }
+    }
+}
+
+impl Framebuffer for BgraFramebuffer<'_> {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        self.buffer[x + y * self.width] = to_bgra(color);
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Color {
+        from_bgra(self.buffer[x + y * self.width])
+    }
+}
+
+/// A 4-bytes-per-pixel RGBA frame, as used by pixels.
+pub struct RgbaFramebuffer<'a> {
+    frame: &'a mut [u8],
+    width: usize,
+    height: usize,
+}
+
+impl<'a> RgbaFramebuffer<'a> {
+    pub fn new(frame: &'a mut [u8], width: usize, height: usize) -> RgbaFramebuffer<'a> {
+        assert_eq!(frame.len(), width * height * 4, "frame size mismatch");
+        RgbaFramebuffer {
+            frame,
+            width,
+            height,
+        }
+    }
+}
+
+impl Framebuffer for RgbaFramebuffer<'_> {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        let i = (x + y * self.width) * 4;
+        self.frame[i..(i + 4)].copy_from_slice(&to_rgba(color));
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Color {
+        let i = (x + y * self.width) * 4;
+        from_rgba([
+            self.frame[i],
+            self.frame[i + 1],
+            self.frame[i + 2],
+            self.frame[i + 3],
+        ])
+    }
+}
+
+/// How a curve's pixels mix with what's already been drawn where curves
+/// cross. Every mode but `Replace` is weighed by the curve's opacity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Blend {
+    /// Overwrites the pixel outright, so the last curve stepped wins.
+    #[default]
+    Replace,
+    /// Adds the colors, so crossings get brighter.
+    Add,
+    /// Paints the color over the pixel, letting it show through by
+    /// `1 - opacity`.
+    Over,
+    /// Multiplies the colors, so crossings get darker.
+    Multiply,
+    /// Keeps the brighter of each channel.
+    Max,
+}
+
+/// Names accepted when parsing a [`Blend`].
+pub const BLEND_NAMES: [&str; 5] = ["replace", "add", "over", "multiply", "max"];
+
+impl Blend {
+    /// Mixes `color` into the pixel `under` it.
+    pub fn apply(self, under: Color, color: Color, opacity: f64) -> Color {
+        let channel = |under: f64, color: f64| {
+            let mixed = match self {
+                Blend::Replace => return color,
+                Blend::Add => under + color,
+                Blend::Over => color,
+                Blend::Multiply => under * color,
+                Blend::Max => under.max(color),
+            };
+            (under + (mixed - under) * opacity).clamp(0.0, 1.0)
+        };
+        (
+            channel(under.0, color.0),
+            channel(under.1, color.1),
+            channel(under.2, color.2),
+        )
+    }
+}
+
+impl FromStr for Blend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Blend, String> {
+        match s.trim() {
+            "replace" => Ok(Blend::Replace),
+            "add" => Ok(Blend::Add),
+            "over" => Ok(Blend::Over),
+            "multiply" => Ok(Blend::Multiply),
+            "max" => Ok(Blend::Max),
+            s => Err(format!(
+                "unknown blend mode `{s}`, expected one of: {}",
+                BLEND_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Blend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Blend::Replace => "replace",
+            Blend::Add => "add",
+            Blend::Over => "over",
+            Blend::Multiply => "multiply",
+            Blend::Max => "max",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{BLUE, RED};
+
+    #[test]
+    fn plots_bgra_pixels() {
+        let mut buffer = vec![0; 3 * 2];
+        let mut framebuffer = BgraFramebuffer::new(&mut buffer, 3, 2);
+        framebuffer.plot((1, 0), RED);
+        framebuffer.plot((2, 1), BLUE);
+        assert_eq!(buffer, [0, 0xffff0000, 0, 0, 0, 0xff0000ff]);
+    }
+
+    #[test]
+    fn plots_rgba_pixels() {
+        let mut frame = vec![0; 2 * 2 * 4];
+        let mut framebuffer = RgbaFramebuffer::new(&mut frame, 2, 2);
+        framebuffer.plot((0, 1), RED);
+        assert_eq!(frame[8..12], [255, 0, 0, 255]);
+        assert_eq!(frame.iter().filter(|&&byte| byte != 0).count(), 2);
+    }
+
+    #[test]
+    fn clears_every_pixel() {
+        let mut buffer = vec![0; 3 * 2];
+        BgraFramebuffer::new(&mut buffer, 3, 2).clear(BLUE);
+        assert_eq!(buffer, [0xff0000ff; 6]);
+    }
+
+    #[test]
+    fn drops_pixels_outside() {
+        let mut buffer = vec![0; 2 * 2];
+        let mut framebuffer = BgraFramebuffer::new(&mut buffer, 2, 2);
+        for position in [(-1, 0), (0, -1), (2, 0), (0, 2), (isize::MIN, isize::MAX)] {
+            framebuffer.plot(position, RED);
+        }
+        assert_eq!(buffer, [0; 4]);
+    }
+
+    #[test]
+    fn blends_by_mode_and_opacity() {
+        let under = (0.5, 0.25, 0.0);
+        let color = (0.25, 1.0, 0.5);
+        assert_eq!(Blend::Replace.apply(under, color, 0.1), color);
+        assert_eq!(Blend::Add.apply(under, color, 1.0), (0.75, 1.0, 0.5));
+        assert_eq!(Blend::Over.apply(under, color, 0.5), (0.375, 0.625, 0.25));
+        assert_eq!(Blend::Multiply.apply(under, color, 1.0), (0.125, 0.25, 0.0));
+        assert_eq!(Blend::Max.apply(under, color, 1.0), (0.5, 1.0, 0.5));
+        assert_eq!(Blend::Add.apply(under, color, 0.0), under);
+    }
+
+    #[test]
+    fn blends_into_the_framebuffer() {
+        let mut frame = vec![0; 2 * 4];
+        let mut framebuffer = RgbaFramebuffer::new(&mut frame, 2, 1);
+        framebuffer.plot((0, 0), RED);
+        framebuffer.blend((0, 0), BLUE, Blend::Add, 1.0);
+        framebuffer.blend((1, 0), BLUE, Blend::Over, 0.2);
+        framebuffer.blend((2, 0), BLUE, Blend::Add, 1.0);
+        assert_eq!(frame, [255, 0, 255, 255, 0, 0, 51, 255]);
+    }
+
+    #[test]
+    fn blend_modes_parse_and_display() {
+        let modes = [
+            Blend::Replace,
+            Blend::Add,
+            Blend::Over,
+            Blend::Multiply,
+            Blend::Max,
+        ];
+        for (blend, name) in modes.into_iter().zip(BLEND_NAMES) {
+            assert_eq!(blend.to_string(), name);
+            assert_eq!(name.parse::<Blend>(), Ok(blend));
+        }
+        assert!("screen".parse::<Blend>().is_err());
+    }
+}
@@ -0,0 +1,630 @@
+//! Gradients, built in code or parsed from strings like
+//! `"#ff0000 0%, #ff8800 50%, #ff0000 100%"`.
+//!
+//! A gradient string is a comma-separated list of colors, each optionally
+//! followed by its position as a percentage or a fraction. Colors are `#rgb`,
+//! `#rrggbb` or a basic CSS color name. Positions left out are spread evenly
+//! between their neighbours, with the first and last defaulting to the ends.
+//! The list can start with `in <space>`, naming the [`ColorSpace`] to blend
+//! in, as in `"in oklab, #440154, #fde725"`.
+
+use crate::color::{Color, ColorSpace, BLACK, BLUE, CYAN, GREEN, MAGENTA, RED, WHITE, YELLOW};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GradientStop {
+    pub depth: f64,
+    pub color: Color,
+}
+
+/// Stops to look colors up in, the color space to blend between them in and
+/// what to do with depths outside `0.0..=1.0`.
+///
+/// The stops are checked when the gradient is made, so looking up a color
+/// never fails: depths before the first stop or after the last get the end
+/// colors.
+#[derive(Clone, Debug)]
+pub struct Gradient {
+    stops: Vec<GradientStop>,
+    space: ColorSpace,
+    extend: Extend,
+}
+
+/// How a gradient treats depths outside `0.0..=1.0`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Extend {
+    /// Holds the color at the nearest end.
+    #[default]
+    Clamp,
+    /// Starts over from the beginning.
+    Repeat,
+    /// Goes back and forth.
+    Mirror,
+}
+
+/// Names accepted when parsing an [`Extend`].
+pub const EXTEND_NAMES: [&str; 3] = ["clamp", "repeat", "mirror"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GradientError {
+    NoStops,
+    /// A stop's depth is infinite or NaN.
+    BadDepth {
+        index: usize,
+        depth: f64,
+    },
+    /// A stop comes before the one in front of it.
+    Unsorted {
+        index: usize,
+    },
+    /// A stop's color has a channel outside `0.0..=1.0`.
+    BadColor {
+        index: usize,
+        color: Color,
+    },
+}
+
+impl fmt::Display for GradientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GradientError::NoStops => write!(f, "a gradient needs at least one stop"),
+            GradientError::BadDepth { index, depth } => {
+                write!(f, "gradient stop {index} has a bad depth {depth}")
+            }
+            GradientError::Unsorted { index } => write!(
+                f,
+                "gradient stop {index} comes before the one in front of it, \
+                 stops must be sorted by depth"
+            ),
+            GradientError::BadColor { index, color } => write!(
+                f,
+                "gradient stop {index} has color {color:?} with a channel outside 0.0 to 1.0"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GradientError {}
+
+impl Gradient {
+    /// A gradient blending in sRGB, like plain stops always have, and
+    /// clamping depths to `0.0..=1.0`.
+    pub fn new(stops: Vec<GradientStop>) -> Result<Gradient, GradientError> {
+        if stops.is_empty() {
+            return Err(GradientError::NoStops);
+        }
+        for (index, stop) in stops.iter().enumerate() {
+            if !stop.depth.is_finite() {
+                return Err(GradientError::BadDepth {
+                    index,
+                    depth: stop.depth,
+                });
+            }
+            if index > 0 && stop.depth < stops[index - 1].depth {
+                return Err(GradientError::Unsorted { index });
+            }
+            let (r, g, b) = stop.color;
+            if ![r, g, b].iter().all(|c| (0.0..=1.0).contains(c)) {
+                return Err(GradientError::BadColor {
+                    index,
+                    color: stop.color,
+                });
+            }
+        }
+        Ok(Gradient {
+            stops,
+            space: ColorSpace::Srgb,
+            extend: Extend::Clamp,
+        })
+    }
+
+    pub fn with_space(mut self, space: ColorSpace) -> Gradient {
+        self.space = space;
+        self
+    }
+
+    pub fn with_extend(mut self, extend: Extend) -> Gradient {
+        self.extend = extend;
+        self
+    }
+
+    pub fn stops(&self) -> &[GradientStop] {
+        &self.stops
+    }
+
+    pub fn space(&self) -> ColorSpace {
+        self.space
+    }
+
+    pub fn extend(&self) -> Extend {
+        self.extend
+    }
+
+    /// Looks up a built-in gradient or palette by name.
+    pub fn named(name: &str) -> Option<Gradient> {
+        if let Some(stops) = built_in(name) {
+            return Some(Gradient::new(stops).expect("built-in gradients are valid"));
+        }
+        PALETTES
+            .iter()
+            .find(|(palette, _)| *palette == name)
+            .map(|(_, spec)| parse(spec).expect("built-in palettes parse"))
+    }
+
+    pub fn color_at(&self, depth: f64) -> Color {
+        let depth = match self.extend {
+            Extend::Clamp => depth.clamp(0.0, 1.0),
+            Extend::Repeat => depth.rem_euclid(1.0),
+            Extend::Mirror => {
+                let depth = depth.rem_euclid(2.0);
+                if depth > 1.0 {
+                    2.0 - depth
+                } else {
+                    depth
+                }
+            }
+        };
+        // the first stop at or after `depth`, NaN ends up at the start
+        let i = self.stops.partition_point(|stop| stop.depth < depth);
+        if i == 0 {
+            return self.stops[0].color;
+        }
+        let Some(after) = self.stops.get(i) else {
+            return self.stops[i - 1].color;
+        };
+        let before = &self.stops[i - 1];
+        let t = (depth - before.depth) / (after.depth - before.depth);
+        self.space.lerp(before.color, after.color, t)
+    }
+}
+
+impl FromStr for Extend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Extend, String> {
+        match s.trim() {
+            "clamp" => Ok(Extend::Clamp),
+            "repeat" => Ok(Extend::Repeat),
+            "mirror" => Ok(Extend::Mirror),
+            s => Err(format!(
+                "unknown gradient extend mode `{s}`, expected one of: {}",
+                EXTEND_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Extend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Extend::Clamp => "clamp",
+            Extend::Repeat => "repeat",
+            Extend::Mirror => "mirror",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Gradient {
+    type Err = String;
+
+    /// Parses a gradient name or a gradient string.
+    fn from_str(s: &str) -> Result<Gradient, String> {
+        let s = s.trim();
+        if let Some(gradient) = Gradient::named(s) {
+            return Ok(gradient);
+        }
+        if !s.contains(',') {
+            return Err(format!(
+                "unknown gradient `{s}`, expected one of: {}, or a list of colors like \
+                 `#ff0000 0%, #ff8800 50%, #ff0000 100%`",
+                GRADIENT_NAMES.join(", ")
+            ));
+        }
+        parse(s)
+    }
+}
+
+fn parse(s: &str) -> Result<Gradient, String> {
+    let mut parts: Vec<&str> = s.split(',').map(str::trim).collect();
+    let mut space = ColorSpace::Srgb;
+    if let Some(name) = parts[0].strip_prefix("in ") {
+        space = name.parse()?;
+        parts.remove(0);
+    }
+    if parts.len() < 2 {
+        return Err("a gradient needs at least two colors".to_string());
+    }
+
+    let mut colors = Vec::new();
+    let mut depths = Vec::new();
+    for part in parts {
+        let mut words = part.split_whitespace();
+        let color = words.next().ok_or("empty gradient stop")?;
+        colors.push(parse_color(color)?);
+        depths.push(words.next().map(parse_position).transpose()?);
+        if let Some(extra) = words.next() {
+            return Err(format!("unexpected `{extra}` in gradient stop `{part}`"));
+        }
+    }
+
+    // spread missing positions evenly between the known ones
+    let last = depths.len() - 1;
+    depths[0] = Some(depths[0].unwrap_or(0.0));
+    depths[last] = Some(depths[last].unwrap_or(1.0));
+    let mut start = 0;
+    for end in 1..=last {
+        if let Some(to) = depths[end] {
+            let from = depths[start].unwrap();
+            for (i, depth) in depths[start + 1..end].iter_mut().enumerate() {
+                let t = (i + 1) as f64 / (end - start) as f64;
+                *depth = Some(from + (to - from) * t);
+            }
+            start = end;
+        }
+    }
+
+    let mut stops: Vec<GradientStop> = colors
+        .into_iter()
+        .zip(depths)
+        .map(|(color, depth)| GradientStop {
+            depth: depth.unwrap(),
+            color,
+        })
+        .collect();
+    if stops.windows(2).any(|w| w[0].depth > w[1].depth) {
+        return Err("gradient stops must be in order".to_string());
+    }
+    // hold the end colors out to the ends, like CSS does
+    if stops[0].depth > 0.0 {
+        stops.insert(
+            0,
+            GradientStop {
+                depth: 0.0,
+                ..stops[0]
+            },
+        );
+    }
+    if stops[stops.len() - 1].depth < 1.0 {
+        stops.push(GradientStop {
+            depth: 1.0,
+            ..stops[stops.len() - 1]
+        });
+    }
+    Ok(Gradient::new(stops)
+        .map_err(|err| err.to_string())?
+        .with_space(space))
+}
+
+/// Parses `50%` or `0.5`, which must be between the ends of the gradient.
+fn parse_position(s: &str) -> Result<f64, String> {
+    let position = match s.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().map(|p| p / 100.0),
+        None => s.parse::<f64>(),
+    }
+    .map_err(|_| format!("bad gradient position `{s}`"))?;
+    if !(0.0..=1.0).contains(&position) {
+        return Err(format!("gradient position `{s}` is outside 0% to 100%"));
+    }
+    Ok(position)
+}
+
+/// Parses `#rgb`, `#rrggbb` or a basic CSS color name.
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    let named = match s.to_ascii_lowercase().as_str() {
+        "black" => Some(BLACK),
+        "white" => Some(WHITE),
+        "gray" | "grey" => Some((0.5, 0.5, 0.5)),
+        "red" => Some(RED),
+        "orange" => Some((1.0, 165.0 / 255.0, 0.0)),
+        "yellow" => Some(YELLOW),
+        "lime" => Some(GREEN),
+        "green" => Some((0.0, 0.5, 0.0)),
+        "cyan" | "aqua" => Some(CYAN),
+        "blue" => Some(BLUE),
+        "purple" => Some((0.5, 0.0, 0.5)),
+        "magenta" | "fuchsia" => Some(MAGENTA),
+        "pink" => Some((1.0, 192.0 / 255.0, 203.0 / 255.0)),
+        _ => None,
+    };
+    if let Some(color) = named {
+        return Ok(color);
+    }
+
+    let bad = || format!("bad color `{s}`, expected `#rgb`, `#rrggbb` or a color name");
+    let hex = s.strip_prefix('#').ok_or_else(bad)?;
+    let digits: Vec<u8> = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()
+        .ok_or_else(bad)?;
+    let [r, g, b] = match digits[..] {
+        [r, g, b] => [r * 17, g * 17, b * 17],
+        [r1, r0, g1, g0, b1, b0] => [r1 * 16 + r0, g1 * 16 + g0, b1 * 16 + b0],
+        _ => return Err(bad()),
+    };
+    Ok((r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0))
+}
+
+/// A gradient that goes from `a` to `b` and back again, so it wraps cleanly.
+pub fn two_color_gradient(a: Color, b: Color) -> Vec<GradientStop> {
+    vec![
+        GradientStop {
+            depth: 0.0,
+            color: a,
+        },
+        GradientStop {
+            depth: 1.0 / 2.0,
+            color: b,
+        },
+        GradientStop {
+            depth: 1.0,
+            color: a,
+        },
+    ]
+}
+
+pub fn solid_gradient(color: Color) -> Vec<GradientStop> {
+    vec![
+        GradientStop { depth: 0.0, color },
+        GradientStop { depth: 1.0, color },
+    ]
+}
+
+/// Red, yellow, green, cyan, blue, magenta and back to red.
+pub fn hsv_gradient() -> Vec<GradientStop> {
+    [RED, YELLOW, GREEN, CYAN, BLUE, MAGENTA, RED]
+        .iter()
+        .enumerate()
+        .map(|(i, &color)| GradientStop {
+            depth: i as f64 / 6.0,
+            color,
+        })
+        .collect()
+}
+
+/// Names accepted by [`named_gradient`].
+pub const GRADIENT_NAMES: [&str; 14] = [
+    "red-orange",
+    "blurple-pink",
+    "blue",
+    "gray",
+    "hsv",
+    "rainbow",
+    "white",
+    "spectrum",
+    "fire",
+    "ocean",
+    "grayscale",
+    "viridis",
+    "magma",
+    "plasma",
+];
+
+/// Palettes written as gradient strings. The matplotlib ones are sampled at
+/// even steps.
+const PALETTES: [(&str, &str); 7] = [
+    (
+        "spectrum",
+        "in oklch, #ff0000, #ff8800, #ffee00, #00cc44, #0088ff, #8833ff, #ff0000",
+    ),
+    (
+        "fire",
+        "#000000, #7a0000, #e03000, #ff9000, #ffe060, #ffffe0",
+    ),
+    (
+        "ocean",
+        "in oklab, #000c24, #003a70, #0080b0, #40c8e0, #d8fcff",
+    ),
+    ("grayscale", "#000000, #ffffff"),
+    (
+        "viridis",
+        "#440154, #472c7a, #3b518b, #2c718e, #21908d, #27ad81, #5cc863, #aadc32, #fde725",
+    ),
+    (
+        "magma",
+        "#000004, #1c1044, #4f127b, #812581, #b5367a, #e55064, #fb8761, #fec287, #fcfdbf",
+    ),
+    (
+        "plasma",
+        "#0d0887, #5302a3, #8b0aa5, #b83289, #db5c68, #f48849, #febd2a, #f0f921",
+    ),
+];
+
+/// The stops of a built-in gradient or palette.
+pub fn named_gradient(name: &str) -> Option<Vec<GradientStop>> {
+    Gradient::named(name).map(|gradient| gradient.stops)
+}
+
+fn built_in(name: &str) -> Option<Vec<GradientStop>> {
+    match name {
+        "red-orange" => Some(two_color_gradient(
+            RED,
+            (1.0, 136.0 / 255.0, 0.0), // orange
+        )),
+        "blurple-pink" => Some(two_color_gradient(
+            (80.0 / 255.0, 0.0, 1.0),           // blurple
+            (187.0 / 255.0, 0.0, 80.0 / 255.0), // pinkish
+        )),
+        "blue" => Some(two_color_gradient(
+            (153.0 / 255.0 / 5.0, 204.0 / 255.0 / 5.0, 1.0 / 5.0),
+            (0.0, 176.0 / 255.0, 240.0 / 255.0), // 00b0f0
+        )),
+        "gray" => Some(two_color_gradient((0.1, 0.1, 0.1), (0.6, 0.6, 0.6))),
+        "hsv" | "rainbow" => Some(hsv_gradient()),
+        "white" => Some(solid_gradient(WHITE)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::BLACK;
+
+    #[test]
+    fn blends_between_stops() {
+        assert_eq!(Gradient::new(hsv_gradient()).unwrap().color_at(0.0), RED);
+        assert_eq!(
+            Gradient::new(hsv_gradient()).unwrap().color_at(1.0 / 12.0),
+            (1.0, 0.5, 0.0)
+        );
+        assert_eq!(Gradient::new(hsv_gradient()).unwrap().color_at(1.0), RED);
+    }
+
+    #[test]
+    fn two_color_gradients_wrap() {
+        let gradient = Gradient::new(two_color_gradient(RED, BLUE)).unwrap();
+        assert_eq!(gradient.color_at(0.5), BLUE);
+        assert_eq!(gradient.color_at(0.25), (0.5, 0.0, 0.5));
+        assert_eq!(gradient.color_at(1.0), RED);
+        assert_eq!(
+            Gradient::new(solid_gradient(WHITE)).unwrap().color_at(0.3),
+            WHITE
+        );
+    }
+
+    #[test]
+    fn blends_in_the_gradient_space() {
+        let stops = two_color_gradient(BLACK, WHITE);
+        assert_eq!(
+            Gradient::new(stops.clone()).unwrap().color_at(0.25),
+            (0.5, 0.5, 0.5)
+        );
+        for space in [ColorSpace::LinearRgb, ColorSpace::Oklab] {
+            let gradient = Gradient::new(stops.clone()).unwrap().with_space(space);
+            assert_eq!(gradient.color_at(0.25), space.lerp(BLACK, WHITE, 0.5));
+        }
+    }
+
+    fn depths(gradient: &Gradient) -> Vec<f64> {
+        gradient.stops().iter().map(|stop| stop.depth).collect()
+    }
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("#f80"), Ok((1.0, 136.0 / 255.0, 0.0)));
+        assert_eq!(parse_color("#FF8800"), Ok((1.0, 136.0 / 255.0, 0.0)));
+        assert_eq!(parse_color("Aqua"), Ok(CYAN));
+        for bad in ["ff8800", "#ff88", "#ggg", "#", "chartreuse"] {
+            assert!(parse_color(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn spreads_missing_positions_evenly() {
+        let gradient: Gradient = "red, lime 25%, blue, white, black".parse().unwrap();
+        assert_eq!(depths(&gradient), [0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(gradient.stops()[2].color, BLUE);
+        assert_eq!(gradient.space(), ColorSpace::Srgb);
+    }
+
+    #[test]
+    fn holds_end_colors_out_to_the_ends() {
+        let gradient: Gradient = "red 0.2, blue 80%".parse().unwrap();
+        assert_eq!(depths(&gradient), [0.0, 0.2, 0.8, 1.0]);
+        assert_eq!(gradient.color_at(0.1), RED);
+        assert_eq!(gradient.color_at(0.9), BLUE);
+    }
+
+    #[test]
+    fn parses_the_color_space() {
+        let gradient: Gradient = "in oklch, red, blue".parse().unwrap();
+        assert_eq!(gradient.space(), ColorSpace::Oklch);
+        assert_eq!(depths(&gradient), [0.0, 1.0]);
+    }
+
+    #[test]
+    fn rejects_bad_gradient_strings() {
+        for bad in [
+            "red",
+            "in oklab, red",
+            "in lab, red, blue",
+            "red 50%, blue 20%",
+            "red 120%, blue",
+            "red half, blue",
+            "red 0% extra, blue",
+            "red, , blue",
+        ] {
+            assert!(bad.parse::<Gradient>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn every_name_is_built_in() {
+        for name in GRADIENT_NAMES {
+            let gradient: Gradient = name.parse().unwrap();
+            assert_eq!(depths(&gradient).first(), Some(&0.0), "{name}");
+            assert_eq!(depths(&gradient).last(), Some(&1.0), "{name}");
+        }
+        // the rainbow is the hsv wheel under another name
+        let pairs = |stops: Vec<GradientStop>| -> Vec<_> {
+            stops.iter().map(|stop| (stop.depth, stop.color)).collect()
+        };
+        let rainbow = named_gradient("rainbow").unwrap();
+        assert_eq!(pairs(rainbow), pairs(hsv_gradient()));
+        let spectrum = Gradient::named("spectrum").unwrap();
+        assert_eq!(spectrum.space(), ColorSpace::Oklch);
+    }
+
+    fn stop(depth: f64, color: Color) -> GradientStop {
+        GradientStop { depth, color }
+    }
+
+    #[test]
+    fn rejects_bad_stops() {
+        assert_eq!(Gradient::new(vec![]).unwrap_err(), GradientError::NoStops);
+        assert!(matches!(
+            Gradient::new(vec![stop(0.0, RED), stop(f64::NAN, BLUE)]),
+            Err(GradientError::BadDepth { index: 1, .. })
+        ));
+        assert!(matches!(
+            Gradient::new(vec![stop(f64::INFINITY, RED)]),
+            Err(GradientError::BadDepth { index: 0, .. })
+        ));
+        assert_eq!(
+            Gradient::new(vec![stop(0.5, RED), stop(0.2, BLUE)]).unwrap_err(),
+            GradientError::Unsorted { index: 1 }
+        );
+        assert_eq!(
+            Gradient::new(vec![stop(0.0, (1.2, 0.0, 0.0))]).unwrap_err(),
+            GradientError::BadColor {
+                index: 0,
+                color: (1.2, 0.0, 0.0)
+            }
+        );
+    }
+
+    #[test]
+    fn extends_past_the_ends() {
+        let gradient = Gradient::new(vec![stop(0.0, BLACK), stop(1.0, WHITE)]).unwrap();
+        let gray = |v| (v, v, v);
+        assert_eq!(gradient.color_at(-0.5), BLACK);
+        assert_eq!(gradient.color_at(1.25), WHITE);
+        assert_eq!(gradient.color_at(f64::NAN), BLACK);
+
+        let gradient = gradient.with_extend(Extend::Repeat);
+        assert_eq!(gradient.color_at(1.25), gray(0.25));
+        assert_eq!(gradient.color_at(-0.25), gray(0.75));
+
+        let gradient = gradient.with_extend(Extend::Mirror);
+        assert_eq!(gradient.color_at(1.25), gray(0.75));
+        assert_eq!(gradient.color_at(-0.25), gray(0.25));
+        assert_eq!(gradient.color_at(2.25), gray(0.25));
+    }
+
+    #[test]
+    fn extend_modes_parse_and_display() {
+        for (extend, name) in [Extend::Clamp, Extend::Repeat, Extend::Mirror]
+            .into_iter()
+            .zip(EXTEND_NAMES)
+        {
+            assert_eq!(extend.to_string(), name);
+            assert_eq!(name.parse::<Extend>(), Ok(extend));
+        }
+        assert!("wrap".parse::<Extend>().is_err());
+    }
+}
@@ -0,0 +1,365 @@
+//! Iterators over a curve that don't need a framebuffer: the turns it makes,
+//! the segments it draws and the pixels it covers.
+//!
+//! ```
+//! use dragon_curve_core::{color, solid_gradient, Config, Gradient, State, Turn, TurnSequence};
+//!
+//! let turns: Vec<Turn> = TurnSequence::dragon().take(3).collect();
+//! assert_eq!(turns, [Turn::R, Turn::R, Turn::L]);
+//!
+//! let config = Config { segment_length: 10 };
+//! let gradient = Gradient::new(solid_gradient(color::WHITE)).unwrap();
+//! let state = State::new((0, 0), (1, 0), gradient, 0, 0);
+//! let ends: Vec<_> = state.segments(config).take(2).map(|s| s.end).collect();
+//! assert_eq!(ends, [(10.0, 0.0), (10.0, 10.0)]);
+//! ```
+
+use crate::color::Color;
+use crate::corner::{rounded_point, Corner};
+use crate::curve::{terdragon_turn, Config, State, Turn};
+use crate::paperfolding::Folds;
+
+/// The endless sequence of turns made by a dragon, paperfolding or terdragon
+/// curve, starting with the turn after the first segment.
+#[derive(Clone, Debug)]
+pub struct TurnSequence {
+    rule: Rule,
+    n: u64,     // number of turns made so far
+    state: u64, // for the dragon, bit k is flipped by each turn at level k
+}
+
+#[derive(Clone, Debug)]
+enum Rule {
+    /// The Heighway dragon, turning by the bits of the turn counter.
+    Dragon,
+    /// A generalized paperfolding curve, turning by `Folds::turn_at`.
+    Paperfolding(Folds),
+    /// The terdragon, turning by `terdragon_turn`.
+    Terdragon,
+}
+
+impl TurnSequence {
+    fn new(rule: Rule) -> TurnSequence {
+        TurnSequence {
+            rule,
+            n: 0,
+            state: 0,
+        }
+    }
+
+    pub fn dragon() -> TurnSequence {
+        TurnSequence::new(Rule::Dragon)
+    }
+
+    pub fn paperfolding(folds: Folds) -> TurnSequence {
+        TurnSequence::new(Rule::Paperfolding(folds))
+    }
+
+    pub fn terdragon() -> TurnSequence {
+        TurnSequence::new(Rule::Terdragon)
+    }
+
+    /// The folds that produce this sequence, or `None` for the terdragon,
+    /// which isn't a paperfolding curve.
+    pub fn folds(&self) -> Option<Folds> {
+        match &self.rule {
+            Rule::Dragon => Some(Folds::dragon()),
+            Rule::Paperfolding(folds) => Some(folds.clone()),
+            Rule::Terdragon => None,
+        }
+    }
+
+    /// Skips ahead (or back) so the next turn is the one after segment `n`.
+    pub fn seek(&mut self, n: u64) {
+        self.n = n;
+        // bit k is set when there have been an odd number of turns at level k
+        self.state = (0..63)
+            .filter(|k| ((n >> k) - (n >> (k + 1))) & 1 == 1)
+            .fold(0, |state, k| state | 1 << k);
+    }
+
+    /// The turn `next` would return, without moving on.
+    pub fn peek(&self) -> Turn {
+        self.clone().next().unwrap()
+    }
+
+    /// The turn `next` last returned, or `None` before the first turn.
+    pub fn previous(&self) -> Option<Turn> {
+        let mut turns = self.clone();
+        turns.seek(self.n.checked_sub(1)?);
+        turns.next()
+    }
+}
+
+impl Iterator for TurnSequence {
+    type Item = Turn;
+
+    fn next(&mut self) -> Option<Turn> {
+        let current_turn = match &self.rule {
+            Rule::Dragon => {
+                // bits that differ when you increment the turn counter
+                let bits = self.n ^ (self.n + 1);
+                // most significant bit
+                let bit = (bits >> 1) + 1;
+
+                let current_turn = if (self.state & bit) != 0 {
+                    Turn::L
+                } else {
+                    Turn::R
+                };
+
+                self.state ^= bit; // flip the bit
+                current_turn
+            }
+            Rule::Paperfolding(folds) => folds.turn_at(self.n + 1),
+            Rule::Terdragon => terdragon_turn(self.n + 1),
+        };
+        self.n += 1;
+        Some(current_turn)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// A straight run of the curve between two turns, in pixel coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    pub start: (f64, f64),
+    pub end: (f64, f64),
+    /// Number of pixels into the curve where the segment starts.
+    pub t: usize,
+    /// Number of pixels of the segment drawn, which falls short of the whole
+    /// segment where the curve finishes or restarts part way along it.
+    pub pixels: usize,
+    /// Color of the middle of the segment.
+    pub color: Color,
+    /// The rounded corners cutting off either end, for curves drawn with a
+    /// corner radius.
+    pub corners: [Option<Corner>; 2],
+}
+
+impl Segment {
+    /// The point `distance` pixels along the segment, following the arcs
+    /// of its rounded corners.
+    pub fn point_at(&self, distance: f64) -> (f64, f64) {
+        rounded_point(self.start, self.end, self.corners, distance)
+    }
+}
+
+/// The drawn segments of a curve, from [`State::segments`].
+///
+/// Ignores the curve's countdown, walking it from where the state currently
+/// is. Starts over from the beginning the other way each time the curve
+/// restarts, and ends where it finishes or a whole run draws nothing. Ends
+/// at either of those fall part way along a segment, which is then only
+/// partly drawn. Endpoints are computed a segment at a time, so off the
+/// right-angle grid they can differ by a fraction of a pixel from the
+/// pixels [`State::step`] plots.
+pub struct Segments {
+    pub(crate) state: State,
+    pub(crate) config: Config,
+}
+
+impl Iterator for Segments {
+    type Item = Segment;
+
+    fn next(&mut self) -> Option<Segment> {
+        let mut starts = 0; // number of times the curve has been at its start
+        loop {
+            let (segment, drawing) = self.state.next_segment(&self.config)?;
+            if drawing {
+                return Some(segment);
+            }
+            if segment.t == 0 {
+                starts += 1;
+                if starts > 1 {
+                    return None; // a whole run with nothing to draw
+                }
+            }
+        }
+    }
+}
+
+/// The pixels a curve draws and their colors, from [`State::pixels`].
+///
+/// Yields exactly what [`State::step`] does, skipping the steps that draw
+/// nothing. Ends when the curve finishes, or when it restarts and a whole
+/// run in between draws nothing, as an L-system that only moves does;
+/// otherwise it goes on forever.
+pub struct Pixels {
+    pub(crate) state: State,
+    pub(crate) config: Config,
+}
+
+impl Iterator for Pixels {
+    type Item = ((isize, isize), Color);
+
+    fn next(&mut self) -> Option<((isize, isize), Color)> {
+        let mut restarts = 0;
+        while !self.state.finished() {
+            let t = self.state.t();
+            if let Some(pixel) = self.state.step(&self.config) {
+                return Some(pixel);
+            }
+            if self.state.t() < t {
+                restarts += 1;
+                if restarts > 1 {
+                    return None; // a whole run with nothing to draw
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradient::{hsv_gradient, Gradient};
+    use crate::lsystem::LSystem;
+
+    fn state() -> State {
+        State::new((0, 0), (1, 0), Gradient::new(hsv_gradient()).unwrap(), 0, 0)
+    }
+
+    #[test]
+    fn turn_sequences_follow_their_rules() {
+        let dragon: Vec<Turn> = TurnSequence::dragon().take(200).collect();
+        let folds = Folds::dragon();
+        assert!((1..=200).map(|n| folds.turn_at(n)).eq(dragon));
+
+        let folds: Folds = "RLL".parse().unwrap();
+        let paperfolding = TurnSequence::paperfolding(folds.clone()).take(200);
+        assert!(paperfolding.eq((1..=200).map(|n| folds.turn_at(n))));
+        assert_eq!(TurnSequence::dragon().folds(), Some(Folds::dragon()));
+
+        let terdragon = TurnSequence::terdragon().take(200);
+        assert!(terdragon.eq((1..=200).map(terdragon_turn)));
+        assert_eq!(TurnSequence::terdragon().folds(), None);
+    }
+
+    #[test]
+    fn turn_sequences_seek() {
+        for n in [0, 1, 7, 64, 1000] {
+            let mut sought = TurnSequence::dragon();
+            sought.seek(n);
+            let walked = TurnSequence::dragon().skip(n as usize);
+            assert!(sought.take(100).eq(walked.take(100)), "n = {n}");
+        }
+    }
+
+    #[test]
+    fn segments_join_up() {
+        let config = Config { segment_length: 10 };
+        let segments: Vec<Segment> = state().segments(config).take(4).collect();
+        let ends = segments.iter().map(|segment| segment.end);
+        assert!(ends.eq([(10.0, 0.0), (10.0, 10.0), (0.0, 10.0), (0.0, 20.0)]));
+        for (t, pair) in segments.windows(2).enumerate() {
+            assert_eq!(pair[0].end, pair[1].start);
+            assert_eq!(pair[1].t, 10 * (t + 1));
+        }
+    }
+
+    #[test]
+    fn segments_leave_out_moves() {
+        let system = LSystem::new("FfF", &[], 90.0, 0);
+        let config = Config { segment_length: 2 };
+        let segments: Vec<Segment> = state().with_lsystem(system).segments(config).collect();
+        let starts: Vec<_> = segments.iter().map(|segment| segment.start).collect();
+        assert_eq!(starts, [(0.0, 0.0), (4.0, 0.0)]);
+        assert_eq!(segments[1].t, 4);
+    }
+
+    #[test]
+    fn segments_stop_at_the_length() {
+        let config = Config { segment_length: 10 };
+        let segments: Vec<Segment> = state().with_length(25).segments(config).collect();
+        let cut: Vec<_> = segments.iter().map(|s| (s.t, s.pixels)).collect();
+        assert_eq!(cut, [(0, 10), (10, 10), (20, 5)]);
+        // the last one keeps its whole length, only part of it is drawn
+        assert_eq!(segments[2].end, (0.0, 10.0));
+    }
+
+    #[test]
+    fn segments_start_over_after_the_duration() {
+        let config = Config { segment_length: 10 };
+        let restarting = State::new(
+            (0, 0),
+            (1, 0),
+            Gradient::new(hsv_gradient()).unwrap(),
+            3,
+            14,
+        );
+        let segments: Vec<Segment> = restarting.segments(config).take(4).collect();
+        let cut: Vec<_> = segments.iter().map(|s| (s.t, s.pixels)).collect();
+        assert_eq!(cut, [(0, 10), (10, 5), (0, 10), (10, 5)]);
+        let ends: Vec<_> = segments.iter().map(|s| s.end).collect();
+        assert_eq!(
+            ends,
+            [(10.0, 0.0), (10.0, 10.0), (-10.0, 0.0), (-10.0, -10.0)]
+        );
+    }
+
+    #[test]
+    fn segments_end_when_a_run_draws_nothing() {
+        let config = Config { segment_length: 2 };
+        let moving = State::new((0, 0), (1, 0), Gradient::new(hsv_gradient()).unwrap(), 0, 5);
+        let system = LSystem::new("f+f", &[], 90.0, 0);
+        assert_eq!(
+            moving.clone().with_lsystem(system).segments(config).next(),
+            None
+        );
+        let system = LSystem::new("", &[], 90.0, 0);
+        assert_eq!(moving.with_lsystem(system).segments(config).next(), None);
+    }
+
+    #[test]
+    fn pixels_match_stepping() {
+        let config = Config { segment_length: 3 };
+        let mut stepped = State::new(
+            (0, 0),
+            (1, 0),
+            Gradient::new(hsv_gradient()).unwrap(),
+            5,
+            40,
+        );
+        let expected: Vec<_> = (0..200).filter_map(|_| stepped.step(&config)).collect();
+        let state = State::new(
+            (0, 0),
+            (1, 0),
+            Gradient::new(hsv_gradient()).unwrap(),
+            5,
+            40,
+        );
+        assert!(state.pixels(config).take(expected.len()).eq(expected));
+    }
+
+    #[test]
+    fn pixels_end_with_the_lsystem() {
+        let system = LSystem::new("FfF", &[], 90.0, 0);
+        let config = Config { segment_length: 2 };
+        let pixels: Vec<_> = state()
+            .with_lsystem(system)
+            .pixels(config)
+            .map(|(position, _)| position)
+            .collect();
+        assert_eq!(pixels, [(0, 0), (1, 0), (4, 0), (5, 0)]);
+    }
+
+    #[test]
+    fn pixels_end_when_a_run_draws_nothing() {
+        let config = Config { segment_length: 2 };
+        let restarting =
+            || State::new((0, 0), (1, 0), Gradient::new(hsv_gradient()).unwrap(), 4, 5);
+        let moving = restarting().with_lsystem(LSystem::new("f+f", &[], 90.0, 0));
+        assert_eq!(moving.pixels(config).next(), None);
+        let empty = restarting().with_lsystem(LSystem::new("", &[], 90.0, 0));
+        assert_eq!(empty.pixels(config).next(), None);
+
+        // drawing some of the time keeps it going
+        let drawing = restarting().with_lsystem(LSystem::new("fF", &[], 90.0, 0));
+        assert_eq!(drawing.pixels(config).take(100).count(), 100);
+    }
+}
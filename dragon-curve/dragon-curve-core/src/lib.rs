@@ -0,0 +1,46 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+//! Dragon curve stepping, gradients and framebuffer writing, shared by the
+//! minifb and wasm front-ends.
+
+pub mod bounds;
+pub mod camera;
+pub mod color;
+pub mod coloring;
+pub mod corner;
+pub mod curve;
+pub mod density;
+pub mod fade;
+pub mod framebuffer;
+pub mod gradient;
+pub mod iter;
+pub mod lsystem;
+pub mod paperfolding;
+pub mod scene;
+pub mod spawn;
+pub mod stroke;
+pub mod svg;
+pub mod turtle;
+
+pub use bounds::{dragon_extent, Bounds};
+pub use camera::{accumulate_in_view, update_in_view, Camera};
+pub use color::{Color, ColorSpace, COLOR_SPACE_NAMES};
+pub use coloring::{ColorMapping, COLOR_MAPPING_NAMES};
+pub use corner::Corner;
+pub use curve::{turn, update, Config, State, Turn};
+pub use density::{accumulate, Density, ToneMap, TONE_MAP_NAMES};
+pub use fade::Fade;
+pub use framebuffer::{BgraFramebuffer, Blend, Framebuffer, RgbaFramebuffer, BLEND_NAMES};
+pub use gradient::{
+    hsv_gradient, named_gradient, solid_gradient, two_color_gradient, Extend, Gradient,
+    GradientError, GradientStop, GRADIENT_NAMES,
+};
+pub use iter::{Pixels, Segment, Segments, TurnSequence};
+pub use lsystem::{LSystem, LSYSTEM_NAMES};
+pub use paperfolding::Folds;
+pub use scene::{Scene, SceneError};
+pub use spawn::{drag_direction, modifier_direction, Spawner};
+pub use stroke::{draw_line, Span, Stroke, STROKE_NAMES};
+pub use svg::Svg;
+pub use turtle::Turtle;
@@ -0,0 +1,331 @@
+//! Lindenmayer systems: an axiom and a set of rewrite rules, read as turtle
+//! commands after a number of rewriting iterations.
+//!
+//! Symbols are interpreted as follows, anything else is ignored:
+//!
+//! - a symbol listed in `draw` moves forward one segment, drawing it
+//! - `f` moves forward one segment without drawing
+//! - `+` turns left by `angle`, `-` turns right by `angle`
+//! - `|` turns around
+//! - `[` saves the turtle's position and heading, `]` restores it
+
+use crate::curve::Turn;
+use crate::turtle::Turtle;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Clone, Debug)]
+pub struct LSystem {
+    pub axiom: String,
+    pub rules: HashMap<char, String>,
+    /// Turning angle in degrees.
+    pub angle: f64,
+    /// Symbols that draw a segment.
+    pub draw: String,
+    /// Times the axiom is rewritten, at most [`MAX_ITERATIONS`].
+    pub iterations: usize,
+}
+
+/// Most rewriting iterations allowed. The rewritten string grows
+/// exponentially with them, so past this no curve gets anywhere near its
+/// end; the lazy expansion keeps one frame per iteration on its stack.
+pub const MAX_ITERATIONS: usize = 32;
+
+/// Most symbols read between one segment and the next. Rules that only
+/// rewrite to symbols without a command, like `X -> XX`, can put
+/// exponentially many of them in a row, so an L-system that goes this long
+/// without a segment is treated as having run out of commands.
+pub const MAX_SYMBOLS_PER_SEGMENT: usize = 1 << 20;
+
+/// Names accepted by [`LSystem::named`].
+pub const LSYSTEM_NAMES: [&str; 6] = [
+    "dragon",
+    "levy-c",
+    "hilbert",
+    "koch-snowflake",
+    "sierpinski-arrowhead",
+    "gosper",
+];
+
+impl LSystem {
+    pub fn new(axiom: &str, rules: &[(char, &str)], angle: f64, iterations: usize) -> LSystem {
+        LSystem {
+            axiom: axiom.to_string(),
+            rules: rules
+                .iter()
+                .map(|&(symbol, body)| (symbol, body.to_string()))
+                .collect(),
+            angle,
+            draw: "F".to_string(),
+            iterations,
+        }
+    }
+
+    pub fn with_draw(mut self, draw: &str) -> LSystem {
+        self.draw = draw.to_string();
+        self
+    }
+
+    /// Looks up a built-in curve, with a number of iterations that fits a
+    /// window at one pixel per segment.
+    pub fn named(name: &str) -> Option<LSystem> {
+        let system = match name {
+            "dragon" => LSystem::new("FX", &[('X', "X+YF+"), ('Y', "-FX-Y")], 90.0, 16),
+            "levy-c" => LSystem::new("F", &[('F', "+F--F+")], 45.0, 16),
+            "hilbert" => LSystem::new("A", &[('A', "+BF-AFA-FB+"), ('B', "-AF+BFB+FA-")], 90.0, 8),
+            "koch-snowflake" => LSystem::new("F--F--F", &[('F', "F+F--F+F")], 60.0, 5),
+            "sierpinski-arrowhead" => {
+                LSystem::new("A", &[('A', "B-A-B"), ('B', "A+B+A")], 60.0, 8).with_draw("AB")
+            }
+            "gosper" => LSystem::new(
+                "A",
+                &[('A', "A-B--B+A++AA+B-"), ('B', "+A-BB--B-A++A+B")],
+                60.0,
+                4,
+            )
+            .with_draw("AB"),
+            _ => return None,
+        };
+        Some(system)
+    }
+
+    /// The turtle commands of the fully rewritten string, produced lazily so
+    /// deep iterations don't need to be held in memory. They end early after
+    /// [`MAX_SYMBOLS_PER_SEGMENT`] symbols without a segment.
+    pub fn commands(self: &Rc<LSystem>) -> Commands {
+        Commands {
+            system: Rc::clone(self),
+            stack: vec![Frame {
+                symbol: None,
+                position: 0,
+                depth: 0,
+            }],
+            read: 0,
+        }
+    }
+
+    fn body(&self, symbol: Option<char>) -> &str {
+        match symbol {
+            None => &self.axiom,
+            Some(symbol) => &self.rules[&symbol],
+        }
+    }
+
+    fn command(&self, symbol: char) -> Option<Command> {
+        match symbol {
+            _ if self.draw.contains(symbol) => Some(Command::Draw),
+            'f' => Some(Command::Move),
+            '+' => Some(Command::Turn(Turn::L)),
+            '-' => Some(Command::Turn(Turn::R)),
+            '|' => Some(Command::TurnAround),
+            '[' => Some(Command::Push),
+            ']' => Some(Command::Pop),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Draw,
+    Move,
+    Turn(Turn),
+    TurnAround,
+    Push,
+    Pop,
+}
+
+#[derive(Clone)]
+struct Frame {
+    symbol: Option<char>, // the rule being expanded, `None` for the axiom
+    position: usize,      // byte offset into the rule's body
+    depth: usize,
+}
+
+#[derive(Clone)]
+pub struct Commands {
+    system: Rc<LSystem>,
+    stack: Vec<Frame>,
+    read: usize, // number of symbols read since the last segment
+}
+
+impl Iterator for Commands {
+    type Item = Command;
+
+    fn next(&mut self) -> Option<Command> {
+        loop {
+            let frame = self.stack.last_mut()?;
+            let body = self.system.body(frame.symbol);
+            let Some(symbol) = body[frame.position..].chars().next() else {
+                self.stack.pop();
+                continue;
+            };
+            frame.position += symbol.len_utf8();
+            let depth = frame.depth;
+            self.read += 1;
+            if self.read > MAX_SYMBOLS_PER_SEGMENT {
+                self.stack.clear();
+                return None;
+            }
+
+            if depth < self.system.iterations && self.system.rules.contains_key(&symbol) {
+                self.stack.push(Frame {
+                    symbol: Some(symbol),
+                    position: 0,
+                    depth: depth + 1,
+                });
+            } else if let Some(command) = self.system.command(symbol) {
+                if matches!(command, Command::Draw | Command::Move) {
+                    self.read = 0;
+                }
+                return Some(command);
+            }
+        }
+    }
+}
+
+/// Walks a turtle through the commands of an L-system, one segment at a time.
+#[derive(Clone)]
+pub struct LSystemPath {
+    system: Rc<LSystem>,
+    commands: Commands,
+    stack: Vec<Turtle>,
+    drawing: bool,  // whether the current segment is drawn
+    finished: bool, // whether the commands have run out
+}
+
+impl LSystemPath {
+    pub fn new(system: Rc<LSystem>) -> LSystemPath {
+        LSystemPath {
+            commands: system.commands(),
+            system,
+            stack: Vec::new(),
+            drawing: false,
+            finished: false,
+        }
+    }
+
+    pub fn system(&self) -> &Rc<LSystem> {
+        &self.system
+    }
+
+    /// Whether the current segment is drawn, rather than just moved along.
+    pub fn drawing(&self) -> bool {
+        self.drawing
+    }
+
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Runs commands up to the start of the next segment. Returns `false` once
+    /// the commands run out.
+    pub fn start_segment(&mut self, turtle: &mut Turtle) -> bool {
+        for command in self.commands.by_ref() {
+            match command {
+                Command::Draw | Command::Move => {
+                    self.drawing = command == Command::Draw;
+                    return true;
+                }
+                Command::Turn(current_turn) => turtle.turn(current_turn),
+                Command::TurnAround => turtle.turn_around(),
+                Command::Push => self.stack.push(turtle.clone()),
+                Command::Pop => {
+                    if let Some(saved) = self.stack.pop() {
+                        *turtle = saved;
+                    }
+                }
+            }
+        }
+        self.finished = true;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(system: LSystem) -> Vec<Command> {
+        Rc::new(system).commands().collect()
+    }
+
+    fn draws(system: LSystem) -> usize {
+        Rc::new(system)
+            .commands()
+            .filter(|&command| command == Command::Draw)
+            .count()
+    }
+
+    #[test]
+    fn rewrites_the_axiom() {
+        use Command::*;
+        let system = LSystem::new("F", &[('F', "F+F")], 90.0, 2);
+        let left = Turn(crate::curve::Turn::L);
+        assert_eq!(commands(system), [Draw, left, Draw, left, Draw, left, Draw]);
+    }
+
+    #[test]
+    fn reads_every_command() {
+        use Command::*;
+        let system = LSystem::new("Ff+-|[]xX", &[], 60.0, 3);
+        let (left, right) = (crate::curve::Turn::L, crate::curve::Turn::R);
+        assert_eq!(
+            commands(system),
+            [Draw, Move, Turn(left), Turn(right), TurnAround, Push, Pop]
+        );
+    }
+
+    #[test]
+    fn leaves_symbols_alone_past_the_last_iteration() {
+        // `X` only ever rewrites to more turns, and is ignored once it stops
+        let system = LSystem::new("FX", &[('X', "+X")], 90.0, 3);
+        assert_eq!(commands(system).len(), 1 + 3);
+        assert_eq!(draws(LSystem::new("F", &[('F', "FF")], 90.0, 0)), 1);
+    }
+
+    #[test]
+    fn named_systems_have_their_usual_sizes() {
+        let segments = [
+            ("dragon", 1 << 16),
+            ("levy-c", 1 << 16),
+            ("hilbert", (1 << 16) - 1),
+            ("koch-snowflake", 3 * 4usize.pow(5)),
+            ("sierpinski-arrowhead", 3usize.pow(8)),
+            ("gosper", 7usize.pow(4)),
+        ];
+        assert_eq!(segments.map(|(name, _)| name), LSYSTEM_NAMES);
+        for (name, count) in segments {
+            assert_eq!(draws(LSystem::named(name).unwrap()), count, "{name}");
+        }
+        assert!(LSystem::named("fern").is_none());
+    }
+
+    #[test]
+    fn walks_a_square() {
+        let system = Rc::new(LSystem::new("F+F+F+FfF", &[], 90.0, 0));
+        let mut path = LSystemPath::new(system);
+        let mut turtle = Turtle::new((0, 0), (1, 0), 90.0);
+        let mut pixels = Vec::new();
+        while path.start_segment(&mut turtle) {
+            pixels.push((turtle.position(), path.drawing()));
+            turtle.forward();
+        }
+        // `+` turns left, which is up on screen; three turns leave it heading down
+        let drawn = [(0, 0), (1, 0), (1, -1), (0, -1)].map(|pixel| (pixel, true));
+        assert_eq!(pixels[..4], drawn);
+        assert_eq!(pixels[4..], [((0, 0), false), ((0, 1), true)]);
+    }
+
+    #[test]
+    fn gives_up_on_long_stretches_without_segments() {
+        use Command::*;
+        // 2^32 `X`s between the two segments, which would take ages to read
+        let system = LSystem::new("FXF", &[('X', "XX")], 90.0, MAX_ITERATIONS);
+        assert_eq!(commands(system), [Draw]);
+
+        // but any number of segments is fine, even more than that many
+        let system = LSystem::new("F", &[('F', "FF")], 90.0, 21);
+        assert_eq!(draws(system), 1 << 21);
+    }
+}
@@ -0,0 +1,228 @@
+//! Generalized paperfolding sequences.
+//!
+//! Folding a strip of paper in half `k + 1` times and unfolding it to right
+//! angles gives a curve whose turns are the paperfolding sequence. Folding
+//! the same way every time gives the Heighway dragon; choosing the direction
+//! of each fold separately gives the rest of the family.
+//!
+//! Writing the turn number as `n = 2^k * (2j + 1)`, turn `n` is made by the
+//! `k`-th fold, and is that fold's direction when `j` is even and the
+//! opposite when `j` is odd.
+
+use crate::curve::Turn;
+use std::fmt;
+use std::str::FromStr;
+
+/// The direction of each fold, repeated for as many folds as needed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Folds(Vec<Turn>);
+
+impl Folds {
+    pub fn new(folds: Vec<Turn>) -> Option<Folds> {
+        if folds.is_empty() {
+            None
+        } else {
+            Some(Folds(folds))
+        }
+    }
+
+    /// The folds that give the Heighway dragon.
+    pub fn dragon() -> Folds {
+        Folds(vec![Turn::R])
+    }
+
+    /// Direction of the `k`-th fold.
+    pub fn fold(&self, k: u32) -> Turn {
+        self.0[k as usize % self.0.len()]
+    }
+
+    /// The turn after segment `n - 1`, for `n >= 1`.
+    pub fn turn_at(&self, n: u64) -> Turn {
+        let k = n.trailing_zeros();
+        let j = n >> (k + 1);
+        let fold = self.fold(k);
+        if j & 1 == 0 {
+            fold
+        } else {
+            fold.opposite()
+        }
+    }
+}
+
+impl FromStr for Folds {
+    type Err = String;
+
+    /// Parses a pattern like `RLRRL`.
+    fn from_str(s: &str) -> Result<Folds, String> {
+        let folds = s
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                'L' | 'l' => Ok(Turn::L),
+                'R' | 'r' => Ok(Turn::R),
+                _ => Err(format!("fold `{c}` should be `L` or `R`")),
+            })
+            .collect::<Result<Vec<Turn>, String>>()?;
+        Folds::new(folds).ok_or_else(|| "a fold pattern needs at least one fold".to_string())
+    }
+}
+
+impl fmt::Display for Folds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for fold in &self.0 {
+            write!(f, "{fold:?}")?;
+        }
+        Ok(())
+    }
+}
+
+type Complex = (f64, f64);
+
+fn mul(a: Complex, b: Complex) -> Complex {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+fn add(a: Complex, b: Complex) -> Complex {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+/// Rotation by `angle` degrees in the direction of `turn`, kept exact for
+/// right angles so grid positions come out as whole numbers.
+fn rotation(turn: Turn, angle: f64) -> Complex {
+    let (cos, sin) = if angle == 90.0 {
+        (0.0, 1.0)
+    } else {
+        let radians = angle.to_radians();
+        (radians.cos(), radians.sin())
+    };
+    match turn {
+        Turn::L => (cos, -sin),
+        Turn::R => (cos, sin),
+    }
+}
+
+impl Folds {
+    /// Position after `n` segments and direction of segment `n`, for a curve
+    /// starting at the origin heading along `(1, 0)` with unit segments and
+    /// turning by `angle` degrees.
+    ///
+    /// The first `2^(k+1)` segments are the first `2^k` segments followed by
+    /// the same segments walked backwards and rotated about their end, so
+    /// this takes one step per bit of `n` instead of walking the curve.
+    pub fn point_at(&self, n: u64, angle: f64) -> (Complex, Complex) {
+        if n == 0 {
+            return ((0.0, 0.0), (1.0, 0.0));
+        }
+        let levels = 64 - n.leading_zeros() as usize;
+
+        // ends[k] is the position after 2^k segments
+        let mut ends: Vec<Complex> = vec![(1.0, 0.0)];
+        for k in 0..levels {
+            let rotate = rotation(self.fold(k as u32), angle);
+            ends.push(mul(ends[k], add((1.0, 0.0), rotate)));
+        }
+
+        // the answer is scale * point(m) + offset for the m we're down to
+        let (mut scale, mut offset) = ((1.0, 0.0), (0.0, 0.0));
+        let mut m = n;
+        let position = loop {
+            if m == 0 {
+                break offset;
+            }
+            let k = 63 - m.leading_zeros();
+            let half = 1 << k;
+            if m == half {
+                break add(mul(scale, ends[k as usize]), offset);
+            }
+            // point(half + r) = end + q * (point(half - r) - end), q = -rotation
+            let rotate = rotation(self.fold(k), angle);
+            let q = (-rotate.0, -rotate.1);
+            let end = ends[k as usize];
+            let shift = add(end, mul(q, (-end.0, -end.1)));
+            offset = add(mul(scale, shift), offset);
+            scale = mul(scale, q);
+            m = 2 * half - m;
+        };
+
+        // direction(half + r) = rotation * direction(half - 1 - r)
+        let mut direction = (1.0, 0.0);
+        let mut m = n;
+        while m > 0 {
+            let k = 63 - m.leading_zeros();
+            direction = mul(direction, rotation(self.fold(k), angle));
+            m = (2 << k) - 1 - m;
+        }
+
+        (position, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turns(folds: &Folds, n: u64) -> String {
+        (1..=n).map(|n| format!("{:?}", folds.turn_at(n))).collect()
+    }
+
+    #[test]
+    fn dragon_turns() {
+        assert_eq!(turns(&Folds::dragon(), 15), "RRLRRLLRRRLLRLL");
+    }
+
+    /// Walks `n` segments of the curve one turn at a time.
+    fn walk(folds: &Folds, n: u64, angle: f64) -> (Complex, Complex) {
+        let (mut position, mut direction) = ((0.0, 0.0), (1.0, 0.0));
+        for i in 1..=n {
+            position = add(position, direction);
+            direction = mul(direction, rotation(folds.turn_at(i), angle));
+        }
+        (position, direction)
+    }
+
+    fn close((a, b): (Complex, Complex), (c, d): (Complex, Complex)) -> bool {
+        [a.0 - c.0, a.1 - c.1, b.0 - d.0, b.1 - d.1]
+            .iter()
+            .all(|difference| difference.abs() < 1e-6)
+    }
+
+    #[test]
+    fn each_fold_sets_its_own_turns() {
+        // odd turns come from the first fold, turns 2 and 6 from the second,
+        // turn 4 from the third, which wraps back to the first
+        let folds: Folds = "LR".parse().unwrap();
+        assert_eq!(turns(&folds, 7), "LRRLLLR");
+    }
+
+    #[test]
+    fn point_at_matches_walking() {
+        for pattern in ["R", "RLL", "LRRLR"] {
+            let folds: Folds = pattern.parse().unwrap();
+            for angle in [90.0, 60.0, 120.0] {
+                for n in 0..300 {
+                    assert!(
+                        close(folds.point_at(n, angle), walk(&folds, n, angle)),
+                        "{pattern} at {angle} degrees, segment {n}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn point_at_is_exact_on_the_grid() {
+        let folds = Folds::dragon();
+        let n = (1 << 20) + 12345;
+        assert_eq!(folds.point_at(n, 90.0), walk(&folds, n, 90.0));
+    }
+
+    #[test]
+    fn parses_and_displays_patterns() {
+        let folds: Folds = "r L l".parse().unwrap();
+        assert_eq!(folds, Folds::new(vec![Turn::R, Turn::L, Turn::L]).unwrap());
+        assert_eq!(folds.to_string(), "RLL");
+        assert_eq!(folds.to_string().parse::<Folds>().unwrap(), folds);
+        assert!("".parse::<Folds>().is_err());
+        assert!("RXL".parse::<Folds>().is_err());
+    }
+}
@@ -0,0 +1,973 @@
+//! A declarative description of a set of dragon curves, loaded from TOML or
+//! JSON.
+//!
+//! ```toml
+//! width = 2400
+//! height = 1600
+//! segment_length = 10
+//!
+//! [[curves]]
+//! direction = "right"
+//! gradient = "red-orange"
+//! duration = 50000
+//!
+//! [[curves]]
+//! start = [1200, 800]
+//! direction = [0, 1]
+//! gradient = [
+//!     { depth = 0.0, color = [0.3, 0.0, 1.0] },
+//!     { depth = 1.0, color = [0.7, 0.0, 0.3] },
+//! ]
+//! countdown = 10000
+//! duration = 25000
+//! ```
+
+use crate::bounds::Bounds;
+use crate::color::Color;
+use crate::coloring::ColorMapping;
+use crate::curve::{Config, State};
+use crate::density::ToneMap;
+use crate::fade::Fade;
+use crate::framebuffer::Blend;
+use crate::gradient::{Gradient, GradientStop};
+use crate::lsystem::{LSystem, LSYSTEM_NAMES, MAX_ITERATIONS};
+use crate::paperfolding::Folds;
+use crate::stroke::{Stroke, MAX_WIDTH};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scene {
+    pub width: usize,
+    pub height: usize,
+    #[serde(default = "default_segment_length")]
+    pub segment_length: usize,
+    /// Count visits and tone-map them into a heatmap, like `"log"` or
+    /// `"gamma:2.2"`, instead of plotting each pixel's color.
+    #[serde(default)]
+    pub density: Option<ToneMapSpec>,
+    /// Number of steps for drawn pixels to fade halfway to the background,
+    /// leaving trails behind the curves. Can't be used with `density`.
+    #[serde(default)]
+    pub fade: Option<f64>,
+    pub curves: Vec<Curve>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Curve {
+    /// Starting position, defaults to the center of the canvas.
+    #[serde(default)]
+    pub start: Option<(isize, isize)>,
+    pub direction: Direction,
+    pub gradient: GradientSpec,
+    /// How pixels pick their gradient color, like `"periodic:5000"`,
+    /// defaults to `"log2"`.
+    #[serde(default)]
+    pub coloring: Option<ColoringSpec>,
+    /// How pixels mix with ones already drawn: `"replace"`, `"add"`,
+    /// `"over"`, `"multiply"` or `"max"`, defaults to `"replace"`.
+    #[serde(default)]
+    pub blend: Option<BlendSpec>,
+    /// How strongly pixels are blended in, from 0.0 to 1.0, defaults to 1.0.
+    /// Needs a `blend` other than `"replace"`, which ignores it.
+    #[serde(default)]
+    pub opacity: Option<f64>,
+    /// How steps are drawn: `"pixels"` or an anti-aliased line like
+    /// `"smooth:2.5"`, defaults to `"pixels"`.
+    #[serde(default)]
+    pub stroke: Option<StrokeSpec>,
+    /// Radius of the arcs rounding off each turn, in pixels, defaults to
+    /// sharp corners.
+    #[serde(default)]
+    pub corner_radius: Option<f64>,
+    /// Number of steps to wait before starting.
+    #[serde(default)]
+    pub countdown: usize,
+    /// Number of pixels to draw before restarting, 0 for never.
+    #[serde(default)]
+    pub duration: usize,
+    /// Built-in turn sequence, `"dragon"` unless `folds` or `lsystem` is set.
+    #[serde(default)]
+    pub turns: Option<Turns>,
+    /// Degrees turned at each corner, defaults to the turn sequence's own.
+    #[serde(default)]
+    pub angle: Option<f64>,
+    /// Fold pattern like `"RLL"` for a generalized paperfolding curve.
+    #[serde(default)]
+    pub folds: Option<FoldsSpec>,
+    /// Follow an L-system instead of the dragon curve's turns.
+    #[serde(default)]
+    pub lsystem: Option<LSystemSpec>,
+    /// Number of rewriting iterations for `lsystem`, up to
+    /// [`MAX_ITERATIONS`]. Defaults to the built-in L-system's own count, or
+    /// 1 for one defined in the scene.
+    #[serde(default)]
+    pub iterations: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Turns {
+    Dragon,
+    Terdragon,
+}
+
+/// A fold pattern, written as a string of `L`s and `R`s.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct FoldsSpec(pub Folds);
+
+impl TryFrom<String> for FoldsSpec {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<FoldsSpec, String> {
+        s.parse().map(FoldsSpec)
+    }
+}
+
+/// A color mapping, written as a string like `"heading"` or `"linear:20000"`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ColoringSpec(pub ColorMapping);
+
+impl TryFrom<String> for ColoringSpec {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<ColoringSpec, String> {
+        s.parse().map(ColoringSpec)
+    }
+}
+
+/// A tone map, written as a string like `"equalize"` or `"gamma:2.2"`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ToneMapSpec(pub ToneMap);
+
+impl TryFrom<String> for ToneMapSpec {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<ToneMapSpec, String> {
+        s.parse().map(ToneMapSpec)
+    }
+}
+
+/// A blend mode, written as a string like `"add"`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct BlendSpec(pub Blend);
+
+impl TryFrom<String> for BlendSpec {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<BlendSpec, String> {
+        s.parse().map(BlendSpec)
+    }
+}
+
+/// A stroke, written as a string like `"smooth:2"`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct StrokeSpec(pub Stroke);
+
+impl TryFrom<String> for StrokeSpec {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<StrokeSpec, String> {
+        s.parse().map(StrokeSpec)
+    }
+}
+
+/// Either the name of a built-in L-system or its definition:
+///
+/// ```toml
+/// lsystem = { axiom = "F", rules = { F = "+F--F+" }, angle = 45.0 }
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "RawLSystem")]
+pub struct LSystemSpec(pub LSystem);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawLSystem {
+    Name(String),
+    Definition {
+        axiom: String,
+        rules: HashMap<char, String>,
+        angle: f64,
+        #[serde(default = "default_draw")]
+        draw: String,
+    },
+}
+
+fn default_draw() -> String {
+    "F".to_string()
+}
+
+impl TryFrom<RawLSystem> for LSystemSpec {
+    type Error = String;
+
+    fn try_from(raw: RawLSystem) -> Result<LSystemSpec, String> {
+        match raw {
+            RawLSystem::Name(name) => LSystem::named(&name).ok_or_else(|| {
+                format!(
+                    "unknown L-system `{name}`, expected one of: {}",
+                    LSYSTEM_NAMES.join(", ")
+                )
+            }),
+            RawLSystem::Definition {
+                axiom,
+                rules,
+                angle,
+                draw,
+            } => Ok(LSystem {
+                axiom,
+                rules,
+                angle,
+                draw,
+                iterations: 1,
+            }),
+        }
+        .map(LSystemSpec)
+    }
+}
+
+/// A unit step along one of the axes, written as `"right"`, `"down"`,
+/// `"left"`, `"up"` or `[dx, dy]`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "RawDirection")]
+pub struct Direction(pub (isize, isize));
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDirection {
+    Name(String),
+    Vector((isize, isize)),
+}
+
+impl TryFrom<RawDirection> for Direction {
+    type Error = String;
+
+    fn try_from(raw: RawDirection) -> Result<Direction, String> {
+        match raw {
+            RawDirection::Name(name) => parse_direction(&name),
+            RawDirection::Vector(v) => check_direction(v),
+        }
+        .map(Direction)
+    }
+}
+
+/// A gradient name or string (see [`crate::gradient`]), a list of stops,
+/// or a table giving one of those along with the color space to blend in and
+/// what to do past its ends (`clamp`, `repeat` or `mirror`):
+///
+/// ```toml
+/// gradient = { name = "red-orange", space = "oklab", extend = "mirror" }
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "RawGradient")]
+pub struct GradientSpec(pub Gradient);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawGradient {
+    Name(String),
+    Stops(Vec<GradientStop>),
+    Table {
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        stops: Option<Vec<GradientStop>>,
+        #[serde(default)]
+        space: Option<String>,
+        #[serde(default)]
+        extend: Option<String>,
+    },
+}
+
+impl TryFrom<RawGradient> for GradientSpec {
+    type Error = String;
+
+    fn try_from(raw: RawGradient) -> Result<GradientSpec, String> {
+        let (name, stops, space, extend) = match raw {
+            RawGradient::Name(name) => (Some(name), None, None, None),
+            RawGradient::Stops(stops) => (None, Some(stops), None, None),
+            RawGradient::Table {
+                name,
+                stops,
+                space,
+                extend,
+            } => (name, stops, space, extend),
+        };
+        let mut gradient: Gradient = match (name, stops) {
+            (Some(name), None) => name.parse()?,
+            (None, Some(stops)) => Gradient::new(stops).map_err(|err| err.to_string())?,
+            _ => return Err("a gradient needs exactly one of `name` and `stops`".to_string()),
+        };
+        if let Some(space) = space {
+            gradient = gradient.with_space(space.parse()?);
+        }
+        if let Some(extend) = extend {
+            gradient = gradient.with_extend(extend.parse()?);
+        }
+        Ok(GradientSpec(gradient))
+    }
+}
+
+fn default_segment_length() -> usize {
+    1
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    UnknownFormat(String),
+    Invalid(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "couldn't read scene: {err}"),
+            SceneError::Toml(err) => write!(f, "invalid TOML scene: {err}"),
+            SceneError::Json(err) => write!(f, "invalid JSON scene: {err}"),
+            SceneError::UnknownFormat(ext) => write!(
+                f,
+                "don't know how to read a `{ext}` scene, expected `.toml` or `.json`"
+            ),
+            SceneError::Invalid(msg) => write!(f, "invalid scene: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io(err) => Some(err),
+            SceneError::Toml(err) => Some(err),
+            SceneError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Scene {
+    /// Reads a scene, picking the format from the file extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Scene, SceneError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(SceneError::Io)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Scene::from_toml(&text),
+            Some("json") => Scene::from_json(&text),
+            ext => Err(SceneError::UnknownFormat(ext.unwrap_or("").to_string())),
+        }
+    }
+
+    pub fn from_toml(text: &str) -> Result<Scene, SceneError> {
+        toml::from_str::<Scene>(text)
+            .map_err(SceneError::Toml)?
+            .validate()
+    }
+
+    pub fn from_json(text: &str) -> Result<Scene, SceneError> {
+        serde_json::from_str::<Scene>(text)
+            .map_err(SceneError::Json)?
+            .validate()
+    }
+
+    /// Checks the things serde can't, like the canvas not being empty.
+    pub fn validate(self) -> Result<Scene, SceneError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(SceneError::Invalid(format!(
+                "canvas size {}x{} must not be empty",
+                self.width, self.height
+            )));
+        }
+        if self.segment_length == 0 {
+            return Err(SceneError::Invalid(
+                "segment_length must be at least 1".to_string(),
+            ));
+        }
+        if let Some(half_life) = self.fade {
+            if half_life.is_nan() || half_life <= 0.0 {
+                return Err(SceneError::Invalid(format!(
+                    "fade must be a positive number of steps, got {half_life}"
+                )));
+            }
+            if self.density.is_some() {
+                return Err(SceneError::Invalid(
+                    "`fade` can't be used with `density`".to_string(),
+                ));
+            }
+        }
+        if self.curves.is_empty() {
+            return Err(SceneError::Invalid(
+                "a scene needs at least one curve".to_string(),
+            ));
+        }
+        if let Some(i) = self
+            .curves
+            .iter()
+            .position(|curve| curve.iterations.is_some() && curve.lsystem.is_none())
+        {
+            return Err(SceneError::Invalid(format!(
+                "curve {i} has `iterations` but no `lsystem` to rewrite"
+            )));
+        }
+        if let Some((i, iterations)) = self.curves.iter().enumerate().find_map(|(i, curve)| {
+            curve
+                .iterations
+                .filter(|&iterations| iterations > MAX_ITERATIONS)
+                .map(|iterations| (i, iterations))
+        }) {
+            return Err(SceneError::Invalid(format!(
+                "curve {i} has {iterations} iterations, it can have at most {MAX_ITERATIONS}"
+            )));
+        }
+        if let Some(i) = self.curves.iter().position(|curve| {
+            let paths = [
+                curve.turns.is_some(),
+                curve.folds.is_some(),
+                curve.lsystem.is_some(),
+            ];
+            paths.iter().filter(|&&set| set).count() > 1
+        }) {
+            return Err(SceneError::Invalid(format!(
+                "curve {i} can only have one of `turns`, `folds` and `lsystem`"
+            )));
+        }
+        if let Some((i, opacity)) = self.curves.iter().enumerate().find_map(|(i, curve)| {
+            curve
+                .opacity
+                .filter(|opacity| !(0.0..=1.0).contains(opacity))
+                .map(|opacity| (i, opacity))
+        }) {
+            return Err(SceneError::Invalid(format!(
+                "curve {i} has opacity {opacity}, it must be between 0.0 and 1.0"
+            )));
+        }
+        if let Some(i) = self.curves.iter().position(|curve| {
+            let replaces = matches!(curve.blend, None | Some(BlendSpec(Blend::Replace)));
+            replaces && curve.opacity.is_some_and(|opacity| opacity < 1.0)
+        }) {
+            return Err(SceneError::Invalid(format!(
+                "curve {i} has an opacity but replaces what's under it, which ignores opacity; \
+                 pick a `blend` like \"over\""
+            )));
+        }
+        if let Some((i, width)) = self
+            .curves
+            .iter()
+            .enumerate()
+            .find_map(|(i, curve)| match curve.stroke {
+                Some(StrokeSpec(Stroke::Smooth { width }))
+                    if !(width.is_finite() && width > 0.0 && width <= MAX_WIDTH) =>
+                {
+                    Some((i, width))
+                }
+                _ => None,
+            })
+        {
+            return Err(SceneError::Invalid(format!(
+                "curve {i} has stroke width {width}, it must be above 0 and up to {MAX_WIDTH}"
+            )));
+        }
+        if let Some((i, radius)) = self.curves.iter().enumerate().find_map(|(i, curve)| {
+            curve
+                .corner_radius
+                .filter(|radius| !(0.0..f64::INFINITY).contains(radius))
+                .map(|radius| (i, radius))
+        }) {
+            return Err(SceneError::Invalid(format!(
+                "curve {i} has corner radius {radius}, it must be finite and non-negative"
+            )));
+        }
+        Ok(self)
+    }
+
+    /// Bounding box of every pixel the curves draw in `steps` steps.
+    pub fn bounds(&self, steps: usize) -> Option<Bounds> {
+        let config = self.config();
+        self.states()
+            .iter()
+            .filter_map(|state| state.bounds(&config, steps))
+            .reduce(Bounds::union)
+    }
+
+    /// Bounding box of the first `segments` segments of every curve, each
+    /// counted from when it starts after its countdown.
+    fn segment_bounds(&self, segments: usize) -> Option<Bounds> {
+        let config = self.config();
+        self.states()
+            .iter()
+            .zip(&self.curves)
+            .filter_map(|(state, curve)| {
+                state.bounds(&config, curve.countdown + segments * self.segment_length)
+            })
+            .reduce(Bounds::union)
+    }
+
+    /// Picks the largest segment length at which the first `segments`
+    /// segments of every curve fit the canvas with `margin` pixels to spare,
+    /// and moves the curves' starts to center them. Returns the number of
+    /// steps that draws them, counting the longest countdown, or 0 for no
+    /// segments, leaving the scene as it is.
+    pub fn fit(&mut self, segments: usize, margin: usize) -> usize {
+        if segments == 0 {
+            return 0;
+        }
+        let room = (
+            self.width.saturating_sub(2 * margin) as f64,
+            self.height.saturating_sub(2 * margin) as f64,
+        );
+        let fits = |scene: &mut Scene, segment_length: usize| {
+            scene.segment_length = segment_length;
+            scene
+                .segment_bounds(segments)
+                .is_none_or(|b| b.width() + 1.0 <= room.0 && b.height() + 1.0 <= room.1)
+        };
+
+        // the curves grow about in proportion to the segment length, so the
+        // size at 1 gives a bound to search under
+        let mut low = 1;
+        self.segment_length = 1;
+        let mut high = match self.segment_bounds(segments) {
+            Some(bounds) => {
+                let size = (bounds.width() + 1.0).max(bounds.height() + 1.0);
+                (2.0 * room.0.max(room.1) / size) as usize + 2
+            }
+            None => 2,
+        };
+        // a single segment longer than the canvas can't fit, but curves that
+        // draw nothing always do
+        let limit = self.width.max(self.height);
+        while high <= limit && fits(self, high) {
+            (low, high) = (high, high * 2);
+        }
+        let mut high = high.min(limit + 1);
+        while high - low > 1 {
+            let middle = (low + high) / 2;
+            if fits(self, middle) {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        self.segment_length = low;
+
+        if let Some(bounds) = self.segment_bounds(segments) {
+            let (x, y) = bounds.center();
+            let canvas = (self.width as f64 / 2.0, self.height as f64 / 2.0);
+            let offset = (
+                (canvas.0 - x - 0.5).round() as isize,
+                (canvas.1 - y - 0.5).round() as isize,
+            );
+            let center = self.center();
+            for curve in &mut self.curves {
+                let start = curve.start.unwrap_or(center);
+                curve.start = Some((start.0 + offset.0, start.1 + offset.1));
+            }
+        }
+        let countdown = self.curves.iter().map(|curve| curve.countdown).max();
+        countdown.unwrap_or(0) + segments * low
+    }
+
+    pub fn center(&self) -> (isize, isize) {
+        ((self.width / 2) as isize, (self.height / 2) as isize)
+    }
+
+    pub fn config(&self) -> Config {
+        Config {
+            segment_length: self.segment_length,
+        }
+    }
+
+    /// How to tone-map visit counts, or `None` to plot colors directly.
+    pub fn tone_map(&self) -> Option<ToneMap> {
+        self.density.map(|ToneMapSpec(tone_map)| tone_map)
+    }
+
+    /// Fading toward `background`, or `None` if pixels stay as drawn.
+    pub fn fade(&self, background: Color) -> Option<Fade> {
+        self.fade.map(|half_life| Fade::new(half_life, background))
+    }
+
+    pub fn states(&self) -> Vec<State> {
+        self.curves
+            .iter()
+            .enumerate()
+            .map(|(i, curve)| {
+                let state = State::new(
+                    curve.start.unwrap_or(self.center()),
+                    curve.direction.0,
+                    curve.gradient.0.clone(),
+                    curve.countdown,
+                    curve.duration,
+                )
+                .with_curve_index(i, self.curves.len());
+                let state = match curve.coloring {
+                    Some(ColoringSpec(coloring)) => state.with_coloring(coloring),
+                    None => state,
+                };
+                let state = match curve.blend {
+                    Some(BlendSpec(blend)) => state.with_blend(blend),
+                    None => state,
+                };
+                let state = match curve.opacity {
+                    Some(opacity) => state.with_opacity(opacity),
+                    None => state,
+                };
+                let state = match curve.stroke {
+                    Some(StrokeSpec(stroke)) => state.with_stroke(stroke),
+                    None => state,
+                };
+                let state = match curve.corner_radius {
+                    Some(radius) => state.with_corner_radius(radius),
+                    None => state,
+                };
+                let state = match (&curve.lsystem, &curve.folds, curve.turns) {
+                    (Some(LSystemSpec(system)), _, _) => {
+                        let mut system = system.clone();
+                        system.iterations = curve.iterations.unwrap_or(system.iterations);
+                        state.with_lsystem(system)
+                    }
+                    (None, Some(FoldsSpec(folds)), _) => state.with_folds(folds.clone()),
+                    (None, None, Some(Turns::Terdragon)) => state.with_terdragon(),
+                    (None, None, _) => state,
+                };
+                match curve.angle {
+                    Some(angle) => state.with_angle(angle),
+                    None => state,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Parses a position written as `x,y`.
+pub fn parse_point(s: &str) -> Result<(isize, isize), String> {
+    let (x, y) = s
+        .split_once(',')
+        .ok_or_else(|| format!("expected `x,y`, got `{s}`"))?;
+    let x = x.trim().parse().map_err(|e| format!("bad x `{x}`: {e}"))?;
+    let y = y.trim().parse().map_err(|e| format!("bad y `{y}`: {e}"))?;
+    Ok((x, y))
+}
+
+/// Parses a direction written as `right`, `down`, `left`, `up` or `dx,dy`.
+pub fn parse_direction(s: &str) -> Result<(isize, isize), String> {
+    match s {
+        "right" => Ok((1, 0)),
+        "down" => Ok((0, 1)),
+        "left" => Ok((-1, 0)),
+        "up" => Ok((0, -1)),
+        _ if s.contains(',') => check_direction(parse_point(s)?),
+        _ => Err(format!(
+            "expected `right`, `down`, `left`, `up` or `dx,dy`, got `{s}`"
+        )),
+    }
+}
+
+fn check_direction(direction: (isize, isize)) -> Result<(isize, isize), String> {
+    match direction {
+        (1, 0) | (0, 1) | (-1, 0) | (0, -1) => Ok(direction),
+        (dx, dy) => Err(format!("`{dx},{dy}` is not a unit step along an axis")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENE: &str = r#"
+        width = 200
+        height = 100
+
+        [[curves]]
+        direction = "right"
+        gradient = "blue"
+
+        [[curves]]
+        start = [20, 10]
+        direction = [0, -1]
+        gradient = [
+            { depth = 0.0, color = [0.3, 0.0, 1.0] },
+            { depth = 1.0, color = [0.7, 0.0, 0.3] },
+        ]
+        countdown = 5
+        duration = 50
+    "#;
+
+    /// `SCENE` with `line` added to its first curve.
+    fn with(line: &str) -> String {
+        SCENE.replacen(
+            "gradient = \"blue\"",
+            &format!("gradient = \"blue\"\n{line}"),
+            1,
+        )
+    }
+
+    #[test]
+    fn loads_toml() {
+        let scene = Scene::from_toml(SCENE).unwrap();
+        assert_eq!(
+            (scene.width, scene.height, scene.segment_length),
+            (200, 100, 1)
+        );
+        let [first, second] = &scene.curves[..] else {
+            panic!("expected two curves");
+        };
+        assert_eq!((first.start, first.direction.0), (None, (1, 0)));
+        assert_eq!((first.countdown, first.duration), (0, 0));
+        assert_eq!(
+            (second.start, second.direction.0),
+            (Some((20, 10)), (0, -1))
+        );
+        assert_eq!((second.countdown, second.duration), (5, 50));
+        assert_eq!(second.gradient.0.stops()[1].color, (0.7, 0.0, 0.3));
+
+        let positions: Vec<_> = scene.states().iter().map(State::position).collect();
+        assert_eq!(positions, [(100, 50), (20, 10)]);
+    }
+
+    #[test]
+    fn loads_json() {
+        let scene = Scene::from_json(
+            r#"{"width": 30, "height": 20, "segment_length": 3,
+                "curves": [{"direction": "up", "gradient": "hsv"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(scene.config().segment_length, 3);
+        assert_eq!(scene.states()[0].direction(), (0.0, -1.0));
+    }
+
+    #[test]
+    fn rejects_bad_scenes() {
+        let bad = [
+            SCENE.replace("width = 200", "width = 0"),
+            SCENE.replace("height = 100", "height = 100\nsegment_length = 0"),
+            SCENE.replace("height = 100", "height = 100\ncolor = 3"),
+            SCENE.replace("[0, -1]", "[1, 1]"),
+            SCENE.replace("\"blue\"", "\"plaid\""),
+            SCENE.replace("depth = 1.0", "depth = nan"),
+            SCENE.replace("depth = 0.0", "depth = 1.5"),
+            with("speed = 2"),
+            "width = 10\nheight = 10\ncurves = []".to_string(),
+        ];
+        for text in bad {
+            assert!(Scene::from_toml(&text).is_err(), "{text}");
+        }
+        // stops don't have to reach the ends, the end colors carry on
+        assert!(Scene::from_toml(&SCENE.replace("depth = 1.0", "depth = 0.5")).is_ok());
+    }
+
+    #[test]
+    fn loads_lsystems() {
+        let scene = Scene::from_toml(&with("lsystem = \"hilbert\"\niterations = 2")).unwrap();
+        let Some(LSystemSpec(system)) = &scene.curves[0].lsystem else {
+            panic!("expected an L-system");
+        };
+        assert_eq!(
+            system.iterations,
+            LSystem::named("hilbert").unwrap().iterations
+        );
+        assert_eq!(scene.curves[0].iterations, Some(2));
+
+        let scene = Scene::from_toml(&with(
+            "lsystem = { axiom = \"F\", rules = { F = \"+F--F+\" }, angle = 45.0 }",
+        ))
+        .unwrap();
+        let Some(LSystemSpec(system)) = &scene.curves[0].lsystem else {
+            panic!("expected an L-system");
+        };
+        assert_eq!((system.iterations, system.draw.as_str()), (1, "F"));
+        assert_eq!(system.rules[&'F'], "+F--F+");
+    }
+
+    #[test]
+    fn rejects_bad_lsystems() {
+        for line in [
+            "lsystem = \"fern\"",
+            "iterations = 3",
+            "lsystem = \"dragon\"\niterations = 33",
+            "lsystem = { axiom = \"F\", angle = 45.0 }",
+        ] {
+            assert!(Scene::from_toml(&with(line)).is_err(), "{line}");
+        }
+        assert!(Scene::from_toml(&with("lsystem = \"dragon\"\niterations = 32")).is_ok());
+    }
+
+    #[test]
+    fn loads_fold_patterns() {
+        let scene = Scene::from_toml(&with("folds = \"RLL\"")).unwrap();
+        let Some(FoldsSpec(folds)) = &scene.curves[0].folds else {
+            panic!("expected a fold pattern");
+        };
+        assert_eq!(folds.to_string(), "RLL");
+        assert!(Scene::from_toml(&with("folds = \"RXL\"")).is_err());
+        assert!(Scene::from_toml(&with("folds = \"R\"\nlsystem = \"dragon\"")).is_err());
+    }
+
+    #[test]
+    fn picks_one_kind_of_turns() {
+        let scene = Scene::from_toml(&with("turns = \"terdragon\"\nangle = 60.0")).unwrap();
+        assert_eq!(scene.curves[0].turns, Some(Turns::Terdragon));
+        assert_eq!(scene.curves[0].angle, Some(60.0));
+        for line in [
+            "turns = \"twindragon\"",
+            "turns = \"terdragon\"\nfolds = \"R\"",
+            "turns = \"dragon\"\nlsystem = \"dragon\"",
+        ] {
+            assert!(Scene::from_toml(&with(line)).is_err(), "{line}");
+        }
+    }
+
+    #[test]
+    fn parses_points_and_directions() {
+        assert_eq!(parse_point(" 3, -4"), Ok((3, -4)));
+        assert!(parse_point("3").is_err());
+        assert!(parse_point("3,x").is_err());
+        assert_eq!(parse_direction("down"), Ok((0, 1)));
+        assert_eq!(parse_direction("0,-1"), Ok((0, -1)));
+        assert!(parse_direction("1,1").is_err());
+        assert!(parse_direction("sideways").is_err());
+    }
+
+    #[test]
+    fn the_shipped_scenes_load() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../scenes");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if let Err(err) = Scene::load(&path) {
+                panic!("{}: {err}", path.display());
+            }
+        }
+    }
+
+    #[test]
+    fn picks_the_format_from_the_extension() {
+        let path = std::env::temp_dir().join(format!("dragon-curve-{}.yaml", std::process::id()));
+        std::fs::write(&path, SCENE).unwrap();
+        let result = Scene::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(SceneError::UnknownFormat(ext)) if ext == "yaml"));
+    }
+
+    fn scene(width: usize, height: usize) -> Scene {
+        Scene::from_toml(&format!(
+            r#"
+            width = {width}
+            height = {height}
+
+            [[curves]]
+            direction = "right"
+            gradient = "blue"
+
+            [[curves]]
+            start = [20, 10]
+            direction = "up"
+            gradient = "red-orange"
+            "#
+        ))
+        .unwrap()
+    }
+
+    fn starts(scene: &Scene) -> Vec<Option<(isize, isize)>> {
+        scene.curves.iter().map(|curve| curve.start).collect()
+    }
+
+    #[test]
+    fn fits_curves_inside_the_margin() {
+        for (width, height, segments, margin) in [(400, 300, 1000, 10), (120, 500, 37, 0)] {
+            let mut scene = scene(width, height);
+            let steps = scene.fit(segments, margin);
+            assert_eq!(steps, segments * scene.segment_length);
+            let bounds = scene.bounds(steps).unwrap();
+            let margin = margin as f64;
+            assert!(bounds.min.0 >= margin - 1.0 && bounds.min.1 >= margin - 1.0);
+            assert!(
+                bounds.max.0 <= width as f64 - margin && bounds.max.1 <= height as f64 - margin
+            );
+
+            // one more pixel per segment no longer fits
+            scene.segment_length += 1;
+            let bigger = scene.bounds(segments * scene.segment_length).unwrap();
+            let room = (width as f64 - 2.0 * margin, height as f64 - 2.0 * margin);
+            assert!(bigger.width() + 1.0 > room.0 || bigger.height() + 1.0 > room.1);
+        }
+    }
+
+    #[test]
+    fn loads_strokes() {
+        let scene = Scene::from_toml(&with("stroke = \"smooth:2.5\"")).unwrap();
+        assert!(matches!(
+            scene.curves[0].stroke,
+            Some(StrokeSpec(Stroke::Smooth { width: 2.5 }))
+        ));
+        for bad in ["\"brush\"", "\"smooth:0\"", "\"smooth:300\""] {
+            assert!(Scene::from_toml(&with(&format!("stroke = {bad}"))).is_err());
+        }
+    }
+
+    #[test]
+    fn fitting_no_segments_leaves_the_scene_alone() {
+        let mut scene = scene(400, 300);
+        assert_eq!(scene.fit(0, 10), 0);
+        assert_eq!(scene.segment_length, 1);
+        assert_eq!(starts(&scene), [None, Some((20, 10))]);
+    }
+
+    #[test]
+    fn fits_each_curve_after_its_countdown() {
+        let mut plain = scene(400, 300);
+        let steps = plain.fit(1000, 10);
+        let mut waiting = scene(400, 300);
+        waiting.curves[1].countdown = 5000;
+        assert_eq!(waiting.fit(1000, 10), 5000 + steps);
+        assert_eq!(waiting.segment_length, plain.segment_length);
+        assert_eq!(starts(&waiting), starts(&plain));
+    }
+
+    #[test]
+    fn fitting_too_much_settles_on_one_pixel() {
+        let mut huge = scene(50, 40);
+        assert_eq!(huge.fit(1 << 40, 0), 1 << 40);
+        assert_eq!(huge.segment_length, 1);
+
+        // a margin wider than the canvas leaves no room at all
+        let mut cramped = scene(10, 10);
+        assert_eq!(cramped.fit(4, 20), 4);
+        assert_eq!(cramped.segment_length, 1);
+    }
+
+    #[test]
+    fn needs_a_blend_for_opacity() {
+        assert!(Scene::from_toml(&with("opacity = 0.5")).is_err());
+        assert!(Scene::from_toml(&with("blend = \"replace\"\nopacity = 0.5")).is_err());
+        assert!(Scene::from_toml(&with("opacity = 1.0")).is_ok());
+        let scene = Scene::from_toml(&with("blend = \"over\"\nopacity = 0.5")).unwrap();
+        assert_eq!(scene.curves[0].opacity, Some(0.5));
+    }
+
+    #[test]
+    fn rejects_bad_corner_radii() {
+        for bad in ["-1.0", "inf", "nan"] {
+            let result = Scene::from_toml(&with(&format!("corner_radius = {bad}")));
+            let Err(SceneError::Invalid(message)) = result else {
+                panic!("expected corner radius {bad} to be rejected");
+            };
+            assert!(
+                message.ends_with("it must be finite and non-negative"),
+                "{message}"
+            );
+        }
+        for good in ["0.0", "2.5"] {
+            assert!(Scene::from_toml(&with(&format!("corner_radius = {good}"))).is_ok());
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Curves started by clicking, on top of the ones a scene describes.
+
+use crate::curve::State;
+use crate::gradient::{Gradient, GRADIENT_NAMES};
+
+/// The directions curves can start in, in the order the modifier keys pick
+/// them: none, shift, control, alt.
+pub const SPAWN_DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+// Drags shorter than this many pixels count as clicks.
+const MIN_DRAG: f64 = 8.0;
+
+/// Makes new curves, each colored by the next built-in gradient, as long as
+/// there is room for them.
+#[derive(Clone, Debug)]
+pub struct Spawner {
+    max_curves: usize, // number of curves that may be going at once
+    length: usize,     // number of pixels each new curve draws
+    palette: usize,    // index into `GRADIENT_NAMES` of the next curve's gradient
+}
+
+impl Spawner {
+    pub fn new(max_curves: usize, length: usize) -> Spawner {
+        Spawner {
+            max_curves,
+            length,
+            palette: 0,
+        }
+    }
+
+    pub fn max_curves(&self) -> usize {
+        self.max_curves
+    }
+
+    /// A curve starting at `position` and heading along `direction`, or
+    /// `None` if `running` curves already fill every slot.
+    pub fn spawn(
+        &mut self,
+        running: usize,
+        position: (isize, isize),
+        direction: (isize, isize),
+    ) -> Option<State> {
+        if running >= self.max_curves {
+            return None;
+        }
+        let gradient = Gradient::named(GRADIENT_NAMES[self.palette]).unwrap();
+        self.palette = (self.palette + 1) % GRADIENT_NAMES.len();
+        Some(State::new(position, direction, gradient, 0, 0).with_length(self.length))
+    }
+}
+
+/// The axis direction closest to a drag from `from` to `to`, or `None` if
+/// the mouse barely moved.
+pub fn drag_direction(from: (f64, f64), to: (f64, f64)) -> Option<(isize, isize)> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    if dx.hypot(dy) < MIN_DRAG {
+        None
+    } else if dx.abs() >= dy.abs() {
+        Some((dx.signum() as isize, 0))
+    } else {
+        Some((0, dy.signum() as isize))
+    }
+}
+
+/// The direction picked by the modifier keys held while clicking: right
+/// with none, down with shift, left with control and up with alt.
+pub fn modifier_direction(shift: bool, control: bool, alt: bool) -> (isize, isize) {
+    match (shift, control, alt) {
+        (true, _, _) => SPAWN_DIRECTIONS[1],
+        (_, true, _) => SPAWN_DIRECTIONS[2],
+        (_, _, true) => SPAWN_DIRECTIONS[3],
+        _ => SPAWN_DIRECTIONS[0],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::Config;
+
+    #[test]
+    fn drags_pick_the_nearest_axis() {
+        assert_eq!(drag_direction((10.0, 10.0), (13.0, 14.0)), None);
+        assert_eq!(drag_direction((10.0, 10.0), (30.0, 15.0)), Some((1, 0)));
+        assert_eq!(drag_direction((10.0, 10.0), (5.0, -20.0)), Some((0, -1)));
+        assert_eq!(drag_direction((10.0, 10.0), (0.0, 10.0)), Some((-1, 0)));
+        assert_eq!(drag_direction((10.0, 10.0), (12.0, 30.0)), Some((0, 1)));
+    }
+
+    #[test]
+    fn modifiers_pick_the_direction() {
+        assert_eq!(modifier_direction(false, false, false), (1, 0));
+        assert_eq!(modifier_direction(true, false, false), (0, 1));
+        assert_eq!(modifier_direction(false, true, true), (-1, 0));
+        assert_eq!(modifier_direction(false, false, true), (0, -1));
+    }
+
+    #[test]
+    fn spawns_while_there_is_room() {
+        let mut spawner = Spawner::new(3, 5);
+        assert!(spawner.spawn(3, (0, 0), (1, 0)).is_none());
+        let mut state = spawner.spawn(2, (4, 6), (0, -1)).unwrap();
+        assert_eq!((state.position(), state.direction()), ((4, 6), (0.0, -1.0)));
+
+        let config = Config { segment_length: 2 };
+        let drawn = (0..10).filter_map(|_| state.step(&config)).count();
+        assert_eq!(drawn, 5);
+        assert!(state.finished());
+    }
+
+    #[test]
+    fn cycles_gradients() {
+        let mut spawner = Spawner::new(1, 0);
+        let first = spawner.spawn(0, (0, 0), (1, 0)).unwrap();
+        for _ in 1..GRADIENT_NAMES.len() {
+            spawner.spawn(0, (0, 0), (1, 0));
+        }
+        let again = spawner.spawn(0, (0, 0), (1, 0)).unwrap();
+        let config = Config { segment_length: 1 };
+        let colors = |mut state: State| state.step(&config).unwrap().1;
+        assert_eq!(colors(first), colors(again));
+    }
+}
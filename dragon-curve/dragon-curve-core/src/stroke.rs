@@ -0,0 +1,258 @@
+//! How a curve's steps become pixels: one whole pixel per step, or an
+//! anti-aliased line of some width between the exact points the curve
+//! passes through, which stays smooth off the right-angle grid and when the
+//! view is scaled.
+//!
+//! Points on the curve are pixel centers, so a one pixel wide smooth stroke
+//! along the grid covers exactly the pixels that pixel stepping draws.
+
+use crate::color::Color;
+use crate::framebuffer::{Blend, Framebuffer};
+use std::fmt;
+use std::str::FromStr;
+
+/// How a curve is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Stroke {
+    /// Plots the pixel each step lands on, rounding off the grid.
+    #[default]
+    Pixels,
+    /// Draws each step as a line `width` pixels wide, shading pixels by how
+    /// much of them the line covers.
+    Smooth { width: f64 },
+}
+
+/// Names accepted when parsing a [`Stroke`].
+pub const STROKE_NAMES: [&str; 2] = ["pixels", "smooth"];
+
+/// Widest smooth stroke, in pixels. Every step shades a square about this
+/// wide, so much wider strokes would grind the curves to a halt.
+pub const MAX_WIDTH: f64 = 256.0;
+
+impl Stroke {
+    /// Draws the stretch of curve `span` into `framebuffer`.
+    pub fn draw(
+        self,
+        framebuffer: &mut impl Framebuffer,
+        span: &Span,
+        color: Color,
+        blend: Blend,
+        opacity: f64,
+    ) {
+        match self {
+            Stroke::Pixels => framebuffer.blend(span.pixel(), color, blend, opacity),
+            Stroke::Smooth { width } => draw_line(framebuffer, span, width, color, blend, opacity),
+        }
+    }
+}
+
+/// The stretch of a curve covered by one step, from the point it starts on
+/// to the point it moves to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub start: (f64, f64),
+    pub end: (f64, f64),
+    pub first: bool, // starts a segment, so it gets a cap at `start`
+    pub last: bool,  // ends a segment, so it gets a cap at `end`
+}
+
+impl Span {
+    /// The pixel pixel stepping draws for this step.
+    pub fn pixel(&self) -> (isize, isize) {
+        (self.start.0.round() as isize, self.start.1.round() as isize)
+    }
+
+    /// The span with both ends moved by `f`.
+    pub fn map(self, f: impl Fn((f64, f64)) -> (f64, f64)) -> Span {
+        Span {
+            start: f(self.start),
+            end: f(self.end),
+            ..self
+        }
+    }
+}
+
+/// Draws `span` as a line `width` pixels wide with square caps where it
+/// starts or ends a segment, so corners are filled in.
+///
+/// Across the line each pixel is shaded by its distance from the center
+/// line. Along it, a pixel belongs to the span its center falls in, so the
+/// steps of a segment tile it without overlapping.
+pub fn draw_line(
+    framebuffer: &mut impl Framebuffer,
+    span: &Span,
+    width: f64,
+    color: Color,
+    blend: Blend,
+    opacity: f64,
+) {
+    let (dx, dy) = (span.end.0 - span.start.0, span.end.1 - span.start.1);
+    let length = dx.hypot(dy);
+    if length == 0.0 || !width.is_finite() {
+        return;
+    }
+    let along = (dx / length, dy / length);
+    let half = width / 2.0;
+    let cap = (half - 0.5).max(0.0);
+    let before = if span.first { cap } else { 0.0 };
+    let after = if span.last { cap } else { 0.0 };
+
+    // only visit pixels inside the framebuffer, however wide the line is
+    let reach = half + cap + 1.0;
+    let left = ((span.start.0.min(span.end.0) - reach).floor() as isize).max(0);
+    let right = ((span.start.0.max(span.end.0) + reach).ceil() as isize)
+        .min(framebuffer.width() as isize - 1);
+    let top = ((span.start.1.min(span.end.1) - reach).floor() as isize).max(0);
+    let bottom = ((span.start.1.max(span.end.1) + reach).ceil() as isize)
+        .min(framebuffer.height() as isize - 1);
+    for y in top..=bottom {
+        for x in left..=right {
+            let (px, py) = (x as f64 - span.start.0, y as f64 - span.start.1);
+            let s = px * along.0 + py * along.1;
+            if s < -before || s >= length + after {
+                continue;
+            }
+            let distance = (px * along.1 - py * along.0).abs();
+            let coverage = (half + 0.5 - distance).clamp(0.0, 1.0);
+            if coverage <= 0.0 {
+                continue;
+            }
+            match blend {
+                // replacing only makes sense for pixels the line covers
+                Blend::Replace if coverage < 1.0 => {
+                    framebuffer.blend((x, y), color, Blend::Over, coverage)
+                }
+                _ => framebuffer.blend((x, y), color, blend, opacity * coverage),
+            }
+        }
+    }
+}
+
+impl FromStr for Stroke {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Stroke, String> {
+        let (name, parameter) = match s.split_once(':') {
+            Some((name, parameter)) => (name.trim(), Some(parameter.trim())),
+            None => (s.trim(), None),
+        };
+        let stroke = match name {
+            "pixels" => Stroke::Pixels,
+            "smooth" => {
+                let width = match parameter {
+                    None => 1.0,
+                    Some(parameter) => match parameter.parse::<f64>() {
+                        Ok(width) if width.is_finite() && width > 0.0 && width <= MAX_WIDTH => width,
+                        _ => {
+                            return Err(format!(
+                                "`smooth` needs a width above 0 and up to {MAX_WIDTH}, got `{parameter}`"
+                            ))
+                        }
+                    },
+                };
+                Stroke::Smooth { width }
+            }
+            _ => {
+                return Err(format!(
+                    "unknown stroke `{name}`, expected one of: {}",
+                    STROKE_NAMES.join(", ")
+                ))
+            }
+        };
+        if parameter.is_some() && !matches!(stroke, Stroke::Smooth { .. }) {
+            return Err(format!("`{name}` doesn't take a parameter"));
+        }
+        Ok(stroke)
+    }
+}
+
+impl fmt::Display for Stroke {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stroke::Pixels => write!(f, "pixels"),
+            Stroke::Smooth { width } => write!(f, "smooth:{width}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{BLACK, WHITE};
+    use crate::framebuffer::RgbaFramebuffer;
+
+    #[test]
+    fn strokes_parse_and_display() {
+        for stroke in [
+            Stroke::Pixels,
+            Stroke::Smooth { width: 1.0 },
+            Stroke::Smooth { width: 2.5 },
+            Stroke::Smooth { width: MAX_WIDTH },
+        ] {
+            assert_eq!(stroke.to_string().parse::<Stroke>(), Ok(stroke));
+        }
+        assert_eq!(
+            "smooth".parse::<Stroke>(),
+            Ok(Stroke::Smooth { width: 1.0 })
+        );
+    }
+
+    #[test]
+    fn rejects_bad_strokes() {
+        for bad in [
+            "brush",
+            "pixels:2",
+            "smooth:0",
+            "smooth:-1",
+            "smooth:inf",
+            "smooth:NaN",
+            "smooth:1e300",
+            "smooth:257",
+            "smooth:wide",
+        ] {
+            assert!(bad.parse::<Stroke>().is_err(), "{bad}");
+        }
+    }
+
+    /// Draws `span` on a black 8 by 8 frame and returns which pixels were
+    /// touched.
+    fn covered(span: Span, width: f64) -> Vec<(usize, usize)> {
+        let mut frame = vec![0; 8 * 8 * 4];
+        let mut framebuffer = RgbaFramebuffer::new(&mut frame, 8, 8);
+        draw_line(&mut framebuffer, &span, width, WHITE, Blend::Replace, 1.0);
+        (0..8)
+            .flat_map(|y| (0..8).map(move |x| (x, y)))
+            .filter(|&(x, y)| framebuffer.get_pixel(x, y) != BLACK)
+            .collect()
+    }
+
+    #[test]
+    fn thin_lines_cover_the_pixels_stepping_does() {
+        let span = |start: f64, first, last| Span {
+            start: (start, 2.0),
+            end: (start + 1.0, 2.0),
+            first,
+            last,
+        };
+        let pixels: Vec<_> = (1..4)
+            .flat_map(|x| covered(span(x as f64, x == 1, x == 3), 1.0))
+            .collect();
+        assert_eq!(pixels, [(1, 2), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn wide_lines_are_capped_and_clipped() {
+        let span = Span {
+            start: (-3.0, 3.0),
+            end: (3.0, 3.0),
+            first: true,
+            last: true,
+        };
+        // three pixels wide, with a pixel of cap past the end, cut off at
+        // the left edge
+        let pixels = covered(span, 3.0);
+        assert_eq!(pixels.len(), 4 * 3);
+        assert!(pixels.iter().all(|&(x, y)| x <= 3 && (2..=4).contains(&y)));
+        assert!(covered(span, f64::INFINITY).is_empty());
+    }
+}
@@ -0,0 +1,431 @@
+//! Vector output: curves written as SVG polylines, which stay sharp at any
+//! zoom and print at any size. Curves with rounded corners are written as
+//! paths of lines and arcs instead.
+//!
+//! Each segment is stroked with the color at its middle. Neighbouring
+//! segments that come out the same color share a polyline, so the file grows
+//! with the number of color changes rather than the number of segments.
+//!
+//! Curves count down and restart as they do on screen. Smooth strokes keep
+//! their width, and opacity and blend modes become `stroke-opacity` and CSS
+//! `mix-blend-mode`; how faithfully those show up is up to the SVG viewer.
+
+use crate::color::{to_rgba, Color, BLACK};
+use crate::corner::Corner;
+use crate::curve::{Config, State};
+use crate::framebuffer::Blend;
+use crate::iter::Segment;
+use crate::stroke::Stroke;
+use std::io::{self, Write};
+
+pub struct Svg {
+    width: usize,
+    height: usize,
+    background: Color,
+    merge_runs: bool,
+    polylines: Vec<Polyline>,
+}
+
+struct Polyline {
+    start: (f64, f64),
+    moves: Vec<Move>,
+    stroke: String, // attributes for the color and look of the line
+}
+
+/// One piece of a polyline, drawn from where the previous one ends.
+#[derive(Clone, Copy)]
+enum Move {
+    Line((f64, f64)),
+    Arc {
+        radius: f64,
+        clockwise: bool,
+        end: (f64, f64),
+    },
+}
+
+impl Move {
+    fn end(&self) -> (f64, f64) {
+        match *self {
+            Move::Line(end) | Move::Arc { end, .. } => end,
+        }
+    }
+}
+
+impl Polyline {
+    fn end(&self) -> (f64, f64) {
+        self.moves.last().map_or(self.start, Move::end)
+    }
+}
+
+impl Svg {
+    /// An empty drawing the size of a `width` by `height` framebuffer, on a
+    /// black background.
+    pub fn new(width: usize, height: usize) -> Svg {
+        Svg {
+            width,
+            height,
+            background: BLACK,
+            merge_runs: false,
+            polylines: Vec::new(),
+        }
+    }
+
+    pub fn with_background(mut self, background: Color) -> Svg {
+        self.background = background;
+        self
+    }
+
+    /// Joins segments that carry on in a straight line into one, colored at
+    /// the middle of the whole run. Smaller files, coarser gradients.
+    pub fn with_merged_runs(mut self, merge_runs: bool) -> Svg {
+        self.merge_runs = merge_runs;
+        self
+    }
+
+    /// Adds what the next `steps` steps of a curve draw, as [`State::step`]
+    /// would draw them.
+    pub fn add_curve(&mut self, state: State, config: Config, steps: usize) {
+        let look = look(&state);
+        for (state, pixels) in state.runs(steps) {
+            self.add_run(state, config, pixels, &look);
+        }
+    }
+
+    /// Adds the next `pixels` pixels of a curve that doesn't restart.
+    fn add_run(&mut self, state: State, config: Config, pixels: usize, look: &str) {
+        let length = config.segment_length;
+        let end = state.t() + pixels;
+        let mut previous = None;
+        let mut segments = state
+            .segments(config)
+            // up to where the steps run out, before the curve starts over
+            .take_while(|s| {
+                let more = s.t < end && previous.is_none_or(|t| s.t > t);
+                previous = Some(s.t);
+                more
+            })
+            .map(|s| {
+                // cut the last segment short where the steps run out
+                let drawn = (end - s.t).min(s.pixels) as f64 / length as f64;
+                let moves = trace(&s, drawn);
+                (s, moves)
+            });
+
+        let Some((mut run, (mut start, mut moves))) = segments.next() else {
+            return;
+        };
+        let mut colors = vec![run.color];
+        for (segment, next) in segments {
+            if self.merge_runs && continues(&run, &segment) {
+                if let (Some(Move::Line(_)), Move::Line(_)) = (moves.last(), next.1[0]) {
+                    moves.pop();
+                }
+                moves.extend(next.1);
+                run = segment;
+                colors.push(run.color);
+                continue;
+            }
+            self.push(start, &moves, colors[colors.len() / 2], look);
+            (run, (start, moves)) = (segment, next);
+            colors = vec![run.color];
+        }
+        self.push(start, &moves, colors[colors.len() / 2], look);
+    }
+
+    fn push(&mut self, start: (f64, f64), moves: &[Move], color: Color, look: &str) {
+        let stroke = format!(r#"stroke="{}"{look}"#, hex(color));
+        if let Some(last) = self.polylines.last_mut() {
+            if last.stroke == stroke && last.end() == start {
+                last.moves.extend_from_slice(moves);
+                return;
+            }
+        }
+        self.polylines.push(Polyline {
+            start,
+            moves: moves.to_vec(),
+            stroke,
+        });
+    }
+
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        let (width, height) = (self.width, self.height);
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        )?;
+        writeln!(
+            out,
+            r#"<rect width="100%" height="100%" fill="{}"/>"#,
+            hex(self.background)
+        )?;
+        // pixels are plotted at their top left corner, so stroke through
+        // their centers to line up with the raster output
+        writeln!(
+            out,
+            r#"<g transform="translate(0.5 0.5)" fill="none" stroke-width="1" stroke-linecap="square" stroke-linejoin="miter">"#
+        )?;
+        for polyline in &self.polylines {
+            let point = |(x, y): (f64, f64)| format!("{},{}", number(x), number(y));
+            if polyline.moves.iter().all(|m| matches!(m, Move::Line(_))) {
+                write!(out, r#"<polyline {} points=""#, polyline.stroke)?;
+                write!(out, "{}", point(polyline.start))?;
+                for m in &polyline.moves {
+                    write!(out, " {}", point(m.end()))?;
+                }
+            } else {
+                write!(out, r#"<path {} d=""#, polyline.stroke)?;
+                write!(out, "M{}", point(polyline.start))?;
+                for m in &polyline.moves {
+                    match *m {
+                        Move::Line(end) => write!(out, " L{}", point(end))?,
+                        Move::Arc {
+                            radius,
+                            clockwise,
+                            end,
+                        } => write!(
+                            out,
+                            " A{r},{r} 0 0 {} {}",
+                            clockwise as u8,
+                            point(end),
+                            r = number(radius)
+                        )?,
+                    }
+                }
+            }
+            writeln!(out, r#""/>"#)?;
+        }
+        writeln!(out, "</g>")?;
+        writeln!(out, "</svg>")
+    }
+}
+
+/// Attributes for how `state`'s stroke, opacity and blend mode differ from
+/// a plain one pixel line, empty if they don't.
+fn look(state: &State) -> String {
+    let mut look = String::new();
+    if let Stroke::Smooth { width } = state.stroke() {
+        if width != 1.0 {
+            look += &format!(r#" stroke-width="{}""#, number(width));
+        }
+    }
+    if state.opacity() < 1.0 {
+        look += &format!(r#" stroke-opacity="{}""#, number(state.opacity()));
+    }
+    let mode = match state.blend() {
+        Blend::Replace | Blend::Over => None,
+        Blend::Add => Some("plus-lighter"),
+        Blend::Multiply => Some("multiply"),
+        Blend::Max => Some("lighten"),
+    };
+    if let Some(mode) = mode {
+        look += &format!(r#" style="mix-blend-mode:{mode}""#);
+    }
+    look
+}
+
+/// Where the first `drawn` of `segment` starts and how it gets to where it
+/// ends, following the arcs of its rounded corners.
+fn trace(segment: &Segment, drawn: f64) -> ((f64, f64), Vec<Move>) {
+    let (start, end) = (segment.start, segment.end);
+    let [first, last] = segment.corners;
+    if first.is_none() && last.is_none() {
+        let end = (
+            start.0 + (end.0 - start.0) * drawn,
+            start.1 + (end.1 - start.1) * drawn,
+        );
+        return (start, vec![Move::Line(end)]);
+    }
+
+    let length = (end.0 - start.0).hypot(end.1 - start.1);
+    let drawn = length * drawn;
+    let arc = |corner: Corner, distance: f64| Move::Arc {
+        radius: corner.radius,
+        clockwise: corner.clockwise(),
+        end: segment.point_at(distance),
+    };
+    let mut moves = Vec::new();
+    let straight_from = first.map_or(0.0, |corner| corner.tangent);
+    let straight_to = last.map_or(length, |corner| length - corner.tangent);
+    if let Some(corner) = first {
+        moves.push(arc(corner, drawn.min(straight_from)));
+    }
+    if drawn > straight_from && straight_to > straight_from {
+        moves.push(Move::Line(segment.point_at(drawn.min(straight_to))));
+    }
+    if let Some(corner) = last.filter(|_| drawn > straight_to) {
+        moves.push(arc(corner, drawn));
+    }
+    (segment.point_at(0.0), moves)
+}
+
+/// Whether `next` starts where `run` ends and heads the same way.
+fn continues(run: &Segment, next: &Segment) -> bool {
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-6;
+    let direction = |s: &Segment| {
+        let (dx, dy) = (s.end.0 - s.start.0, s.end.1 - s.start.1);
+        let length = dx.hypot(dy);
+        (dx / length, dy / length)
+    };
+    let (a, b) = (direction(run), direction(next));
+    close(run.end.0, next.start.0)
+        && close(run.end.1, next.start.1)
+        && close(a.0, b.0)
+        && close(a.1, b.1)
+}
+
+fn hex(color: Color) -> String {
+    let [r, g, b, _] = to_rgba(color);
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Formats a coordinate with at most two decimals and no trailing zeros.
+fn number(x: f64) -> String {
+    let s = format!("{:.2}", x);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    match s {
+        "-0" => "0".to_string(),
+        _ => s.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::WHITE;
+    use crate::gradient::{solid_gradient, Gradient};
+    use crate::lsystem::LSystem;
+
+    fn svg(merge_runs: bool, steps: usize) -> String {
+        let state = State::new(
+            (1, 1),
+            (1, 0),
+            Gradient::new(solid_gradient(WHITE)).unwrap(),
+            0,
+            0,
+        )
+        .with_lsystem(LSystem::new("FFF+F", &[], 90.0, 0));
+        let mut svg = Svg::new(8, 4).with_merged_runs(merge_runs);
+        svg.add_curve(state, Config { segment_length: 2 }, steps);
+        let mut out = Vec::new();
+        svg.write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn polylines(svg: &str) -> Vec<&str> {
+        svg.lines()
+            .filter(|line| line.starts_with("<polyline"))
+            .collect()
+    }
+
+    #[test]
+    fn writes_a_polyline_per_color() {
+        let svg = svg(false, 100);
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="8" height="4" viewBox="0 0 8 4">"#
+        ));
+        assert!(svg.contains(r##"<rect width="100%" height="100%" fill="#000000"/>"##));
+        assert_eq!(
+            polylines(&svg),
+            [r##"<polyline stroke="#ffffff" points="1,1 3,1 5,1 7,1 7,-1"/>"##]
+        );
+        assert!(svg.ends_with("</g>\n</svg>\n"));
+    }
+
+    #[test]
+    fn merges_straight_runs() {
+        assert_eq!(
+            polylines(&svg(true, 100)),
+            [r##"<polyline stroke="#ffffff" points="1,1 7,1 7,-1"/>"##]
+        );
+    }
+
+    #[test]
+    fn stops_where_the_steps_run_out() {
+        assert_eq!(
+            polylines(&svg(false, 5)),
+            [r##"<polyline stroke="#ffffff" points="1,1 3,1 5,1 6,1"/>"##]
+        );
+        assert!(polylines(&svg(false, 0)).is_empty());
+    }
+
+    #[test]
+    fn formats_numbers_briefly() {
+        assert_eq!(number(3.0), "3");
+        assert_eq!(number(0.5), "0.5");
+        assert_eq!(number(1.0 / 3.0), "0.33");
+        assert_eq!(number(-0.001), "0");
+    }
+
+    #[test]
+    fn rounds_corners_with_arcs() {
+        let state = State::new(
+            (1, 1),
+            (1, 0),
+            Gradient::new(solid_gradient(WHITE)).unwrap(),
+            0,
+            0,
+        )
+        .with_corner_radius(1.0);
+        let mut svg = Svg::new(8, 8).with_merged_runs(true);
+        svg.add_curve(state, Config { segment_length: 4 }, 8);
+        let mut out = Vec::new();
+        svg.write(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let paths: Vec<_> = out
+            .lines()
+            .filter(|line| line.starts_with("<path"))
+            .collect();
+        // the turn at the first segment's end is split between the two
+        // segments, and the second segment's turn is drawn up to its end
+        assert_eq!(
+            paths,
+            [
+                r##"<path stroke="#ffffff" d="M1,1 L4,1 A1,1 0 0 1 4.71,1.29 A1,1 0 0 1 5,2 L5,4 A1,1 0 0 1 4.71,4.71"/>"##
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_down_and_restarts() {
+        let state = State::new(
+            (1, 1),
+            (1, 0),
+            Gradient::new(solid_gradient(WHITE)).unwrap(),
+            2,
+            3,
+        )
+        .with_lsystem(LSystem::new("FFF", &[], 90.0, 0));
+        let mut svg = Svg::new(8, 4);
+        svg.add_curve(state, Config { segment_length: 1 }, 2 + 4 + 2);
+        let mut out = Vec::new();
+        svg.write(&mut out).unwrap();
+        // the whole L-system, then back the other way after restarting
+        assert_eq!(
+            polylines(&String::from_utf8(out).unwrap()),
+            [
+                r##"<polyline stroke="#ffffff" points="1,1 2,1 3,1 4,1"/>"##,
+                r##"<polyline stroke="#ffffff" points="1,1 0,1 -1,1"/>"##,
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_the_look_of_the_stroke() {
+        let state = State::new(
+            (1, 1),
+            (1, 0),
+            Gradient::new(solid_gradient(WHITE)).unwrap(),
+            0,
+            0,
+        );
+        assert_eq!(look(&state), "");
+        let state = state
+            .with_stroke(Stroke::Smooth { width: 2.5 })
+            .with_opacity(0.5)
+            .with_blend(Blend::Add);
+        assert_eq!(
+            look(&state),
+            r#" stroke-width="2.5" stroke-opacity="0.5" style="mix-blend-mode:plus-lighter""#
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::curve::{turn, Turn};
+
+/// Something that walks the framebuffer one pixel at a time and turns by a
+/// fixed angle.
+///
+/// Right-angle turtles stay on the integer grid, which is exact and cheap.
+/// Any other angle uses a floating-point position and heading, rounded to
+/// the nearest pixel when plotting.
+#[derive(Clone, Debug)]
+pub enum Turtle {
+    Grid {
+        position: (isize, isize),  // pixel coordinates
+        direction: (isize, isize), // position + direction = next position
+    },
+    Real {
+        position: (f64, f64),
+        heading: f64, // radians, clockwise on screen
+        angle: f64,   // radians turned by each `Turn`
+    },
+}
+
+impl Turtle {
+    /// A turtle at `position` facing along the axis `direction`, turning by
+    /// `angle` degrees.
+    pub fn new(position: (isize, isize), direction: (isize, isize), angle: f64) -> Turtle {
+        if angle == 90.0 {
+            Turtle::Grid {
+                position,
+                direction,
+            }
+        } else {
+            Turtle::Real {
+                position: (position.0 as f64, position.1 as f64),
+                heading: (direction.1 as f64).atan2(direction.0 as f64),
+                angle: angle.to_radians(),
+            }
+        }
+    }
+
+    /// A turtle at an arbitrary point facing along the unit vector
+    /// `direction`, turning by `angle` degrees. Right-angle turtles are
+    /// snapped to the grid.
+    pub fn at(position: (f64, f64), direction: (f64, f64), angle: f64) -> Turtle {
+        if angle == 90.0 {
+            Turtle::Grid {
+                position: (position.0.round() as isize, position.1.round() as isize),
+                direction: (direction.0.round() as isize, direction.1.round() as isize),
+            }
+        } else {
+            Turtle::Real {
+                position,
+                heading: direction.1.atan2(direction.0),
+                angle: angle.to_radians(),
+            }
+        }
+    }
+
+    /// The pixel the turtle is on.
+    pub fn position(&self) -> (isize, isize) {
+        match *self {
+            Turtle::Grid { position, .. } => position,
+            Turtle::Real { position, .. } => {
+                (position.0.round() as isize, position.1.round() as isize)
+            }
+        }
+    }
+
+    /// Exact position, between pixels off the right-angle grid.
+    pub fn point(&self) -> (f64, f64) {
+        match *self {
+            Turtle::Grid { position, .. } => (position.0 as f64, position.1 as f64),
+            Turtle::Real { position, .. } => position,
+        }
+    }
+
+    /// Unit vector the turtle is facing.
+    pub fn direction(&self) -> (f64, f64) {
+        match *self {
+            Turtle::Grid { direction, .. } => (direction.0 as f64, direction.1 as f64),
+            Turtle::Real { heading, .. } => (heading.cos(), heading.sin()),
+        }
+    }
+
+    /// Moves one pixel forward.
+    pub fn forward(&mut self) {
+        match self {
+            Turtle::Grid {
+                position,
+                direction,
+            } => {
+                position.0 += direction.0;
+                position.1 += direction.1;
+            }
+            Turtle::Real {
+                position, heading, ..
+            } => {
+                position.0 += heading.cos();
+                position.1 += heading.sin();
+            }
+        }
+    }
+
+    /// Moves `distance` pixels forward in one go.
+    pub fn forward_by(&mut self, distance: usize) {
+        match self {
+            Turtle::Grid {
+                position,
+                direction,
+            } => {
+                position.0 += direction.0 * distance as isize;
+                position.1 += direction.1 * distance as isize;
+            }
+            Turtle::Real {
+                position, heading, ..
+            } => {
+                position.0 += heading.cos() * distance as f64;
+                position.1 += heading.sin() * distance as f64;
+            }
+        }
+    }
+
+    pub fn turn(&mut self, current_turn: Turn) {
+        match self {
+            Turtle::Grid { direction, .. } => *direction = turn(*direction, current_turn),
+            Turtle::Real { heading, angle, .. } => match current_turn {
+                Turn::L => *heading -= *angle,
+                Turn::R => *heading += *angle,
+            },
+        }
+    }
+
+    pub fn turn_around(&mut self) {
+        match self {
+            Turtle::Grid { direction, .. } => *direction = (-direction.0, -direction.1),
+            Turtle::Real { heading, .. } => *heading += std::f64::consts::PI,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn right_angles_stay_on_the_grid() {
+        let mut turtle = Turtle::new((5, 5), (1, 0), 90.0);
+        assert!(matches!(turtle, Turtle::Grid { .. }));
+        turtle.forward();
+        turtle.turn(Turn::L);
+        turtle.forward();
+        assert_eq!(turtle.position(), (6, 4));
+        turtle.turn_around();
+        assert_eq!(turtle.direction(), (0.0, 1.0));
+    }
+
+    #[test]
+    fn walks_a_hexagon() {
+        let mut turtle = Turtle::new((0, 0), (1, 0), 60.0);
+        let mut corners = Vec::new();
+        for _ in 0..6 {
+            turtle.forward();
+            turtle.turn(Turn::L);
+            corners.push(turtle.position());
+        }
+        // left is up on screen, so the hexagon sits above the start
+        assert_eq!(
+            corners,
+            [(1, 0), (2, -1), (1, -2), (0, -2), (-1, -1), (0, 0)]
+        );
+        let (x, y) = turtle.direction();
+        assert!((x - 1.0).abs() < 1e-9 && y.abs() < 1e-9);
+    }
+}
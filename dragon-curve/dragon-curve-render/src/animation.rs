@@ -0,0 +1,365 @@
+//! Animated output: RGBA frames written one at a time as a GIF, an APNG, a
+//! Y4M stream or a directory of numbered images.
+
+use crate::save_png;
+use clap::ValueEnum;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub enum Animation {
+    Gif(Gif),
+    Apng(png::Writer<BufWriter<File>>),
+    Y4m(Y4m),
+    Sequence(Sequence),
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum FrameFormat {
+    Png,
+    Ppm,
+}
+
+impl Animation {
+    pub fn gif(path: &Path, width: usize, height: usize) -> Result<Animation, Box<dyn Error>> {
+        let too_big =
+            |size: usize| format!("{size} pixels is too big for a GIF, the limit is 65535");
+        let width = u16::try_from(width).map_err(|_| too_big(width))?;
+        let height = u16::try_from(height).map_err(|_| too_big(height))?;
+        let file = File::create(path)?;
+        let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        Ok(Animation::Gif(Gif {
+            encoder,
+            width,
+            height,
+            previous: None,
+        }))
+    }
+
+    pub fn apng(
+        path: &Path,
+        width: usize,
+        height: usize,
+        frames: usize,
+    ) -> Result<Animation, Box<dyn Error>> {
+        let file = File::create(path)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frames as u32, 0)?;
+        Ok(Animation::Apng(encoder.write_header()?))
+    }
+
+    /// A YUV4MPEG2 stream with a frame every `delay` milliseconds.
+    pub fn y4m(
+        mut out: Box<dyn Write>,
+        width: usize,
+        height: usize,
+        delay: u32,
+    ) -> Result<Animation, Box<dyn Error>> {
+        // full-resolution chroma, so single-pixel lines keep their color
+        writeln!(
+            out,
+            "YUV4MPEG2 W{width} H{height} F1000:{} Ip A1:1 C444",
+            delay.max(1)
+        )?;
+        Ok(Animation::Y4m(Y4m {
+            out,
+            delay,
+            planes: vec![0; width * height * 3],
+        }))
+    }
+
+    /// Numbered images in `directory`, a frame every `delay` milliseconds.
+    pub fn sequence(
+        directory: &Path,
+        format: FrameFormat,
+        width: usize,
+        height: usize,
+        delay: u32,
+    ) -> Result<Animation, Box<dyn Error>> {
+        std::fs::create_dir_all(directory)?;
+        Ok(Animation::Sequence(Sequence {
+            directory: directory.to_path_buf(),
+            format,
+            width,
+            height,
+            delay,
+            next: 0,
+        }))
+    }
+
+    /// Adds a frame shown for `delay` milliseconds. Formats with a fixed
+    /// frame rate repeat the frame to make up the time.
+    pub fn add_frame(&mut self, frame: &[u8], delay: u32) -> Result<(), Box<dyn Error>> {
+        match self {
+            Animation::Gif(gif) => gif.add_frame(frame, delay)?,
+            Animation::Apng(writer) => {
+                writer.set_frame_delay(delay.min(u16::MAX as u32) as u16, 1000)?;
+                writer.write_image_data(frame)?;
+            }
+            Animation::Y4m(y4m) => {
+                y4m.convert(frame);
+                for _ in 0..repeats(delay, y4m.delay) {
+                    y4m.out.write_all(b"FRAME\n")?;
+                    y4m.out.write_all(&y4m.planes)?;
+                }
+            }
+            Animation::Sequence(sequence) => {
+                for _ in 0..repeats(delay, sequence.delay) {
+                    sequence.save(frame)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Animation::Gif(gif) => gif.encoder.into_inner()?.flush()?,
+            Animation::Apng(writer) => writer.finish()?,
+            Animation::Y4m(mut y4m) => y4m.out.flush()?,
+            Animation::Sequence(_) => {}
+        }
+        Ok(())
+    }
+}
+
+/// Number of frames at a fixed `frame_delay` that add up to `delay`.
+fn repeats(delay: u32, frame_delay: u32) -> u32 {
+    (delay as f64 / frame_delay.max(1) as f64).round().max(1.0) as u32
+}
+
+pub struct Y4m {
+    out: Box<dyn Write>,
+    delay: u32,
+    planes: Vec<u8>, // Y, then Cb, then Cr
+}
+
+impl Y4m {
+    /// Converts an RGBA frame to studio-range BT.601 Y'CbCr planes, which is
+    /// what players assume a Y4M stream without a color range holds.
+    fn convert(&mut self, frame: &[u8]) {
+        let size = frame.len() / 4;
+        let (y, chroma) = self.planes.split_at_mut(size);
+        let (cb, cr) = chroma.split_at_mut(size);
+        for (i, pixel) in frame.chunks_exact(4).enumerate() {
+            let r = pixel[0] as f64 / 255.0;
+            let g = pixel[1] as f64 / 255.0;
+            let b = pixel[2] as f64 / 255.0;
+            y[i] = (16.0 + 65.481 * r + 128.553 * g + 24.966 * b).round() as u8;
+            cb[i] = (128.0 - 37.797 * r - 74.203 * g + 112.0 * b).round() as u8;
+            cr[i] = (128.0 + 112.0 * r - 93.786 * g - 18.214 * b).round() as u8;
+        }
+    }
+}
+
+pub struct Sequence {
+    directory: PathBuf,
+    format: FrameFormat,
+    width: usize,
+    height: usize,
+    delay: u32,
+    next: usize,
+}
+
+impl Sequence {
+    fn save(&mut self, frame: &[u8]) -> Result<(), Box<dyn Error>> {
+        let extension = match self.format {
+            FrameFormat::Png => "png",
+            FrameFormat::Ppm => "ppm",
+        };
+        let path = self
+            .directory
+            .join(format!("frame-{:05}.{extension}", self.next));
+        self.next += 1;
+        match self.format {
+            FrameFormat::Png => save_png(&path, self.width, self.height, frame)?,
+            FrameFormat::Ppm => {
+                let mut out = BufWriter::new(File::create(&path)?);
+                write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+                for pixel in frame.chunks_exact(4) {
+                    out.write_all(&pixel[..3])?;
+                }
+                out.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct Gif {
+    encoder: gif::Encoder<BufWriter<File>>,
+    width: u16,
+    height: u16,
+    previous: Option<Vec<u8>>,
+}
+
+impl Gif {
+    /// Each frame after the first only covers the rectangle that changed
+    /// since the one before, and is drawn over it.
+    fn add_frame(&mut self, frame: &[u8], delay: u32) -> Result<(), gif::EncodingError> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let (left, top, right, bottom) = match &self.previous {
+            None => (0, 0, width, height),
+            Some(previous) => changed_rect(previous, frame, width, height).unwrap_or((0, 0, 1, 1)),
+        };
+
+        let mut pixels = Vec::with_capacity((right - left) * (bottom - top) * 4);
+        for y in top..bottom {
+            pixels.extend_from_slice(&frame[(y * width + left) * 4..(y * width + right) * 4]);
+        }
+        let mut gif_frame = gif::Frame::from_rgba_speed(
+            (right - left) as u16,
+            (bottom - top) as u16,
+            &mut pixels,
+            10,
+        );
+        gif_frame.left = left as u16;
+        gif_frame.top = top as u16;
+        gif_frame.delay = (delay / 10).min(u16::MAX as u32) as u16; // in hundredths of a second
+        gif_frame.dispose = gif::DisposalMethod::Keep;
+        self.encoder.write_frame(&gif_frame)?;
+
+        match &mut self.previous {
+            Some(previous) => previous.copy_from_slice(frame),
+            None => self.previous = Some(frame.to_vec()),
+        }
+        Ok(())
+    }
+}
+
+/// The smallest `(left, top, right, bottom)` rectangle holding every pixel
+/// that differs between two RGBA frames, or `None` if they're the same.
+fn changed_rect(
+    previous: &[u8],
+    frame: &[u8],
+    width: usize,
+    height: usize,
+) -> Option<(usize, usize, usize, usize)> {
+    let mut rect: Option<(usize, usize, usize, usize)> = None;
+    for y in 0..height {
+        let row = y * width * 4..(y + 1) * width * 4;
+        let (old, new) = (&previous[row.clone()], &frame[row]);
+        if old == new {
+            continue;
+        }
+        let differs = |x: &usize| old[x * 4..x * 4 + 4] != new[x * 4..x * 4 + 4];
+        let first = (0..width).find(differs).unwrap_or(0);
+        let last = (0..width).rev().find(differs).unwrap_or(0);
+        rect = Some(match rect {
+            None => (first, y, last + 1, y + 1),
+            Some((left, top, right, _)) => (left.min(first), top, right.max(last + 1), y + 1),
+        });
+    }
+    rect
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: usize = 5;
+    const HEIGHT: usize = 4;
+
+    fn frame(lit: &[(usize, usize)]) -> Vec<u8> {
+        let mut frame = [0, 0, 0, 255].repeat(WIDTH * HEIGHT);
+        for &(x, y) in lit {
+            frame[(y * WIDTH + x) * 4..][..3].copy_from_slice(&[255, 255, 255]);
+        }
+        frame
+    }
+
+    #[test]
+    fn finds_the_changed_rect() {
+        let rect = |lit: &[(usize, usize)]| changed_rect(&frame(&[]), &frame(lit), WIDTH, HEIGHT);
+        assert_eq!(rect(&[]), None);
+        assert_eq!(rect(&[(2, 1)]), Some((2, 1, 3, 2)));
+        assert_eq!(rect(&[(3, 0), (1, 2)]), Some((1, 0, 4, 3)));
+        assert_eq!(rect(&[(0, 3), (4, 3)]), Some((0, 3, 5, 4)));
+
+        // only color differences count, wherever they are in the pixel
+        let mut alpha = frame(&[]);
+        alpha[(2 * WIDTH + 4) * 4 + 3] = 0;
+        assert_eq!(
+            changed_rect(&frame(&[]), &alpha, WIDTH, HEIGHT),
+            Some((4, 2, 5, 3))
+        );
+    }
+
+    #[test]
+    fn gif_frames_cover_what_changed() {
+        let path = std::env::temp_dir().join(format!("dragon-curve-{}.gif", std::process::id()));
+        let mut gif = Animation::gif(&path, WIDTH, HEIGHT).unwrap();
+        for lit in [&[][..], &[(1, 1), (3, 2)], &[(1, 1), (3, 2)]] {
+            gif.add_frame(&frame(lit), 50).unwrap();
+        }
+        gif.finish().unwrap();
+
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(File::open(&path).unwrap())
+            .unwrap();
+        let mut rects = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 5);
+            rects.push((frame.left, frame.top, frame.width, frame.height));
+        }
+        std::fs::remove_file(&path).unwrap();
+        // an unchanged frame still needs a pixel to carry its delay
+        assert_eq!(rects, [(0, 0, 5, 4), (1, 1, 3, 2), (0, 0, 1, 1)]);
+    }
+
+    #[test]
+    fn converts_to_studio_range_bt601() {
+        let pixels = [
+            [0, 0, 0, 255],
+            [255, 255, 255, 255],
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+        ];
+        let mut y4m = Y4m {
+            out: Box::new(std::io::sink()),
+            delay: 40,
+            planes: vec![0; pixels.len() * 3],
+        };
+        y4m.convert(pixels.as_flattened());
+        let (y, chroma) = y4m.planes.split_at(pixels.len());
+        let (cb, cr) = chroma.split_at(pixels.len());
+        assert_eq!(y, [16, 235, 81, 145, 41]);
+        assert_eq!(cb, [128, 128, 90, 54, 240]);
+        assert_eq!(cr, [128, 128, 240, 34, 110]);
+    }
+
+    #[test]
+    fn y4m_repeats_frames_to_keep_time() {
+        let path = std::env::temp_dir().join(format!("dragon-curve-{}.y4m", std::process::id()));
+        let out = Box::new(File::create(&path).unwrap());
+        let mut y4m = Animation::y4m(out, 2, 1, 40).unwrap();
+        y4m.add_frame(&[255; 8], 100).unwrap();
+        y4m.finish().unwrap();
+        let stream = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let header = b"YUV4MPEG2 W2 H1 F1000:40 Ip A1:1 C444\n";
+        let frame = b"FRAME\n\xeb\xeb\x80\x80\x80\x80";
+        assert_eq!(stream, [&header[..], frame, frame, frame].concat());
+        assert_eq!(repeats(10, 40), 1);
+    }
+
+    #[test]
+    fn writes_numbered_ppms() {
+        let directory =
+            std::env::temp_dir().join(format!("dragon-curve-frames-{}", std::process::id()));
+        let mut frames = Animation::sequence(&directory, FrameFormat::Ppm, 1, 2, 40).unwrap();
+        frames.add_frame(&[1, 2, 3, 255, 4, 5, 6, 255], 80).unwrap();
+        frames.finish().unwrap();
+        let first = std::fs::read(directory.join("frame-00000.ppm")).unwrap();
+        let second = std::fs::read(directory.join("frame-00001.ppm")).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(first, b"P6\n1 2\n255\n\x01\x02\x03\x04\x05\x06");
+        assert_eq!(second, first);
+    }
+}
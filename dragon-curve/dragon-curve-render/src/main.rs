@@ -0,0 +1,388 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+mod animation;
+
+use animation::{Animation, FrameFormat};
+use clap::builder::RangedU64ValueParser;
+use clap::Parser;
+use dragon_curve_core::color::BLACK;
+use dragon_curve_core::scene::ToneMapSpec;
+use dragon_curve_core::{
+    accumulate, update, Config, Density, Fade, Framebuffer, RgbaFramebuffer, Scene, SceneError,
+    State, Svg, ToneMap,
+};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// The scene to draw when none is given.
+const DEFAULT_SCENE: &str = include_str!("../../scenes/four-curves.toml");
+
+/// Renders dragon curves without opening a window and writes the result as a
+/// PNG, or as an SVG if the output ends in `.svg`.
+///
+/// An output ending in `.gif` or `.apng` gets an animation of the curves
+/// growing instead, with a frame every `--frame-interval` steps. For video,
+/// `.y4m` (or `-` for stdout) gets a YUV4MPEG2 stream and a directory gets a
+/// numbered image per frame, either of which ffmpeg can read:
+///
+///     dragon-curve-render --scene scenes/four-spirals.toml -o - | ffmpeg -i - spirals.mp4
+#[derive(Parser)]
+struct Args {
+    /// Where to write the image
+    #[arg(short, long, default_value = "dragon-curve.png")]
+    output: PathBuf,
+
+    /// Scene file (`.toml` or `.json`) [default: four curves from the center]
+    #[arg(long)]
+    scene: Option<PathBuf>,
+
+    /// Overrides the scene's width
+    #[arg(long)]
+    width: Option<usize>,
+
+    /// Overrides the scene's height
+    #[arg(long)]
+    height: Option<usize>,
+
+    /// Overrides the scene's segment length
+    #[arg(long)]
+    segment_length: Option<usize>,
+
+    /// Number of pixels to draw for each curve
+    #[arg(short, long, default_value_t = 1_000_000)]
+    steps: usize,
+
+    /// Count visits and tone-map them into a heatmap instead of plotting
+    /// colors: `log`, `gamma:<gamma>` or `equalize` [default: the scene's]
+    /// (not for SVG)
+    #[arg(long, value_parser = parse_tone_map)]
+    density: Option<ToneMap>,
+
+    /// Number of steps for drawn pixels to fade halfway to the background
+    /// [default: the scene's] (not for SVG)
+    #[arg(long, conflicts_with = "density")]
+    fade: Option<f64>,
+
+    /// Draw the first this many segments of every curve as large as the
+    /// canvas allows, picking the segment length and where the curves start
+    /// (overrides --steps and --segment-length)
+    #[arg(
+        long,
+        conflicts_with = "segment_length",
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+    )]
+    fit: Option<usize>,
+
+    /// Pixels to leave around the curves with --fit
+    #[arg(long, default_value_t = 20)]
+    margin: usize,
+
+    /// Join segments that continue in a straight line (SVG only)
+    #[arg(long)]
+    merge_runs: bool,
+
+    /// Number of frames to animate, for every output but SVG; a still PNG
+    /// is drawn as the last of them
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..))]
+    frames: u32,
+
+    /// Number of pixels each curve advances between frames, which is also
+    /// how often a still PNG's trails fade [default: --steps divided by
+    /// --frames]
+    #[arg(long)]
+    frame_interval: Option<usize>,
+
+    /// Milliseconds to show each frame for, which also sets the frame rate of
+    /// Y4M streams and frame sequences
+    #[arg(long, default_value_t = 40)]
+    delay: u32,
+
+    /// Milliseconds to hold the last frame for before looping
+    #[arg(long, default_value_t = 2000)]
+    hold: u32,
+
+    /// Image format of each frame when the output is a directory
+    #[arg(long, value_enum, default_value_t = FrameFormat::Png)]
+    frame_format: FrameFormat,
+}
+
+impl Args {
+    /// The animation `--output` asks for, or `None` for a still image.
+    fn animation(&self, scene: &Scene) -> Option<Result<Animation, Box<dyn Error>>> {
+        let (width, height) = (scene.width, scene.height);
+        let output = &self.output;
+        if output.as_os_str() == "-" {
+            let stdout = Box::new(BufWriter::new(std::io::stdout()));
+            return Some(Animation::y4m(stdout, width, height, self.delay));
+        }
+        if output.is_dir() || output.to_string_lossy().ends_with('/') {
+            return Some(Animation::sequence(
+                output,
+                self.frame_format,
+                width,
+                height,
+                self.delay,
+            ));
+        }
+        match output.extension().and_then(|ext| ext.to_str()) {
+            Some("gif") => Some(Animation::gif(output, width, height)),
+            Some("apng") => Some(Animation::apng(output, width, height, self.frames as usize)),
+            Some("y4m") => Some(File::create(output).map_err(Into::into).and_then(|file| {
+                Animation::y4m(Box::new(BufWriter::new(file)), width, height, self.delay)
+            })),
+            _ => None,
+        }
+    }
+
+    /// Number of steps between frames.
+    fn frame_interval(&self) -> usize {
+        self.frame_interval
+            .unwrap_or(self.steps / self.frames as usize)
+            .max(1)
+    }
+
+    fn scene(&self) -> Result<Scene, SceneError> {
+        let mut scene = match &self.scene {
+            Some(path) => Scene::load(path)?,
+            None => Scene::from_toml(DEFAULT_SCENE)?,
+        };
+        scene.width = self.width.unwrap_or(scene.width);
+        scene.height = self.height.unwrap_or(scene.height);
+        scene.segment_length = self.segment_length.unwrap_or(scene.segment_length);
+        if let Some(tone_map) = self.density {
+            scene.density = Some(ToneMapSpec(tone_map));
+        }
+        scene.fade = self.fade.or(scene.fade);
+        scene.validate()
+    }
+}
+
+fn main() {
+    let mut args = Args::parse();
+    let mut scene = args.scene().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+    if let Some(segments) = args.fit {
+        args.steps = scene.fit(segments, args.margin);
+    }
+
+    let stdout = args.output.as_os_str() == "-";
+    let result: Result<(), Box<dyn Error>> = match args.animation(&scene) {
+        Some(animation) => {
+            animation.and_then(|animation| write_animation(&args, &scene, animation))
+        }
+        None if args.output.extension().is_some_and(|ext| ext == "svg") => {
+            write_svg(&args, &scene).map_err(Into::into)
+        }
+        None => write_png(&args, &scene).map_err(Into::into),
+    };
+    if let Err(err) = result {
+        eprintln!("failed to write {}: {err}", args.output.display());
+        std::process::exit(1);
+    }
+    if !stdout {
+        println!("Wrote {}", args.output.display());
+    }
+}
+
+fn write_svg(args: &Args, scene: &Scene) -> std::io::Result<()> {
+    let mut svg = Svg::new(scene.width, scene.height).with_merged_runs(args.merge_runs);
+    for state in scene.states() {
+        svg.add_curve(state, scene.config(), args.steps);
+    }
+    let file = File::create(&args.output)?;
+    let mut out = BufWriter::new(file);
+    svg.write(&mut out)?;
+    out.flush()
+}
+
+fn write_animation(
+    args: &Args,
+    scene: &Scene,
+    mut animation: Animation,
+) -> Result<(), Box<dyn Error>> {
+    let mut canvas = Canvas::new(scene);
+    let interval = args.frame_interval();
+    for i in 0..args.frames {
+        canvas.advance(interval);
+        let delay = if i + 1 == args.frames {
+            args.hold
+        } else {
+            args.delay
+        };
+        animation.add_frame(canvas.frame(), delay)?;
+    }
+    animation.finish()
+}
+
+fn write_png(args: &Args, scene: &Scene) -> Result<(), png::EncodingError> {
+    let mut canvas = Canvas::new(scene);
+    // a frame at a time, so trails fade as in the last frame of an animation
+    let interval = args.frame_interval();
+    for start in (0..args.steps).step_by(interval) {
+        canvas.advance(interval.min(args.steps - start));
+    }
+    save_png(&args.output, scene.width, scene.height, canvas.frame())
+}
+
+/// Curves being drawn into an RGBA frame, either directly or by counting
+/// visits to tone-map when the frame is asked for.
+struct Canvas {
+    config: Config,
+    states: Vec<State>,
+    width: usize,
+    height: usize,
+    frame: Vec<u8>,
+    density: Option<(Density, ToneMap)>,
+    fade: Option<Fade>,
+}
+
+impl Canvas {
+    fn new(scene: &Scene) -> Canvas {
+        let (width, height) = (scene.width, scene.height);
+        let mut frame = vec![0; width * height * 4];
+        RgbaFramebuffer::new(&mut frame, width, height).clear(BLACK);
+        Canvas {
+            config: scene.config(),
+            states: scene.states(),
+            width,
+            height,
+            frame,
+            density: scene
+                .tone_map()
+                .map(|tone_map| (Density::new(width, height), tone_map)),
+            fade: scene.fade(BLACK),
+        }
+    }
+
+    /// Moves every curve `steps` pixels along as one frame, fading the trails
+    /// once at the end of it like the viewer does.
+    fn advance(&mut self, steps: usize) {
+        let mut framebuffer = RgbaFramebuffer::new(&mut self.frame, self.width, self.height);
+        for _ in 0..steps {
+            for state in self.states.iter_mut() {
+                match &mut self.density {
+                    Some((density, _)) => accumulate(&self.config, density, state),
+                    None => update(&self.config, &mut framebuffer, state),
+                }
+            }
+        }
+        if let Some(fade) = &mut self.fade {
+            fade.advance(steps, &mut framebuffer);
+        }
+    }
+
+    fn frame(&mut self) -> &[u8] {
+        if let Some((density, tone_map)) = &self.density {
+            let mut framebuffer = RgbaFramebuffer::new(&mut self.frame, self.width, self.height);
+            density.tone_map(*tone_map, &mut framebuffer);
+        }
+        &self.frame
+    }
+}
+
+fn save_png(
+    path: &Path,
+    width: usize,
+    height: usize,
+    frame: &[u8],
+) -> Result<(), png::EncodingError> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(frame)?;
+    writer.finish()
+}
+
+fn parse_tone_map(s: &str) -> Result<ToneMap, String> {
+    s.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(extension: &str, steps: &str) -> PathBuf {
+        let name = format!("dragon-curve-{}.{extension}", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        let output = path.to_str().unwrap();
+        let args = [
+            "", "-o", output, "--width", "5", "--height", "3", "-s", steps,
+        ];
+        let args = Args::try_parse_from(args).unwrap();
+        let scene = args.scene().unwrap();
+        if extension == "svg" {
+            write_svg(&args, &scene).unwrap();
+        } else {
+            write_png(&args, &scene).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn writes_a_png_that_reads_back() {
+        let path = render("png", "1");
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut read = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut read).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((info.width, info.height), (5, 3));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+
+        // one step of each curve only reaches the center
+        for (i, pixel) in read.chunks(4).enumerate() {
+            let lit = pixel[..3] != [0, 0, 0];
+            assert_eq!(lit, i == 5 + 2, "pixel {i}");
+            assert_eq!(pixel[3], 255);
+        }
+    }
+
+    #[test]
+    fn writes_an_svg() {
+        let path = render("svg", "3");
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(svg.starts_with("<svg"));
+        // one polyline or more per curve, each curve starting from the center
+        assert_eq!(svg.matches(r#"points="2,1 "#).count(), 4);
+    }
+
+    #[test]
+    fn fits_at_least_one_segment() {
+        let parse = |fit| Args::try_parse_from(["", "-o", "out.png", "--fit", fit]);
+        assert!(parse("0").is_err());
+        assert_eq!(parse("1000").unwrap().fit, Some(1000));
+    }
+
+    #[test]
+    fn fades_once_a_frame() {
+        let scene = Scene::from_toml(
+            r#"
+            width = 8
+            height = 1
+            segment_length = 8
+            fade = 1.0
+
+            [[curves]]
+            start = [0, 0]
+            direction = "right"
+            gradient = [{ depth = 0.0, color = [1.0, 1.0, 1.0] }]
+            "#,
+        )
+        .unwrap();
+        let mut canvas = Canvas::new(&scene);
+        canvas.advance(4);
+        // every pixel of the frame fades the same, however early it was drawn
+        let red: Vec<u8> = canvas.frame().chunks(4).map(|pixel| pixel[0]).collect();
+        assert!(red[0] > 0 && red[0] < 255);
+        assert_eq!(red[..4], [red[0]; 4]);
+        assert_eq!(red[4..], [0; 4]);
+    }
+}
@@ -1,6 +1,11 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
+use dragon_curve_core::color::BLACK;
+use dragon_curve_core::{
+    accumulate, drag_direction, modifier_direction, update, Density, RgbaFramebuffer, Scene,
+    SceneError, Spawner, State,
+};
 use error_iter::ErrorIter as _;
 use log::error;
 use pixels::{Pixels, SurfaceTexture};
@@ -13,174 +18,15 @@ use winit_input_helper::WinitInputHelper;
 // We stole most of this from:
 // https://github.com/parasyte/pixels/tree/main/examples/minimal-web
 
-const WIDTH: u32 = 2400;
-const HEIGHT: u32 = 1600;
-const SEGMENT_LENGTH: usize = 10;
 const BATCH_SIZE: usize = 100;
 
-pub struct Config {
-    width: isize,
-    height: isize,
-    segment_length: usize,
-}
-
-pub struct State {
-    position: (isize, isize),  // pixel coordinates
-    direction: (isize, isize), // position + direction = next position
-    starting_direction: (isize, isize),
-    starting_position: (isize, isize),
-    segment_progress: usize, // number of pixels into a segment
-    t: usize,                // number of pixels into the curve
-    turn_counter: i64,
-    turn_state: i64,
-    gradient: Vec<GradientStop>,
-    countdown: usize, // number of iterations to wait before starting
-    duration: usize,  // number of pixels to draw before resetting
-}
-
-impl State {
-    fn new(
-        starting_position: (isize, isize),
-        starting_direction: (isize, isize),
-        gradient: Vec<GradientStop>,
-        countdown: usize,
-        duration: usize,
-    ) -> State {
-        return State {
-            starting_position: starting_position,
-            starting_direction: starting_direction,
-            position: starting_position,
-            direction: starting_direction,
-            segment_progress: 0,
-            t: 0,
-            turn_counter: 0,
-            turn_state: 0,
-            gradient: gradient,
-            countdown: countdown,
-            duration: duration,
-        };
-    }
-}
-
-#[derive(Clone, Copy)]
-pub struct GradientStop {
-    depth: f64,
-    color: (f64, f64, f64),
-}
-
-#[derive(Clone, Copy)]
-pub enum Turn {
-    L,
-    R,
-}
-
-fn turn(direction: (isize, isize), turn: Turn) -> (isize, isize) {
-    match turn {
-        Turn::L => (direction.1, -direction.0),
-        Turn::R => (-direction.1, direction.0),
-    }
-}
-
-fn to_rgba(color: (f64, f64, f64)) -> [u8; 4] {
-    let r: u8 = (color.0 * 255.99) as u8;
-    let g: u8 = (color.1 * 255.99) as u8;
-    let b: u8 = (color.2 * 255.99) as u8;
-    let a: u8 = 0xff;
-    [r, g, b, a]
-}
-
-fn lerp_f64(u: f64, v: f64, t: f64) -> f64 {
-    v * t + u * (1.0 - t)
-}
-
-fn lerp_color(u: (f64, f64, f64), v: (f64, f64, f64), t: f64) -> (f64, f64, f64) {
-    (
-        lerp_f64(u.0, v.0, t),
-        lerp_f64(u.1, v.1, t),
-        lerp_f64(u.2, v.2, t),
-    )
-}
-
-fn two_color_gradient(a: (f64, f64, f64), b: (f64, f64, f64)) -> Vec<GradientStop> {
-    vec![
-        GradientStop {
-            depth: 0.0,
-            color: a,
-        },
-        GradientStop {
-            depth: 1.0 / 2.0,
-            color: b,
-        },
-        GradientStop {
-            depth: 1.0,
-            color: a,
-        },
-    ]
-}
-
-fn get_gradient_color(gradient: &Vec<GradientStop>, depth: f64) -> (f64, f64, f64) {
-    for i in 1..gradient.len() {
-        if gradient[i].depth >= depth {
-            let t = (depth - gradient[i - 1].depth) / (gradient[i].depth - gradient[i - 1].depth);
-            return lerp_color(gradient[i - 1].color, gradient[i].color, t);
-        }
-    }
-    panic!("Invalid gradient depth: {:?}", depth);
-}
-
-fn update(config: &Config, frame: &mut [u8], state: &mut State) -> () {
-    if state.countdown > 0 {
-        state.countdown -= 1;
-        return;
-    }
-
-    if state.duration > 0 && state.t > state.duration {
-        state.position = state.starting_position;
-        state.starting_direction = turn(turn(state.starting_direction, Turn::R), Turn::R);
-        state.direction = state.starting_direction;
-        state.segment_progress = 0;
-        state.turn_counter = 0;
-        state.turn_state = 0;
-        state.t = 0;
-    }
-
-    let mut d = (state.t as f64 + 1.0).log2();
-    d -= d.floor();
-    let color = get_gradient_color(&state.gradient, d);
+// Curves added by clicking: how many curves can be going at once, and how
+// many pixels each new one draws before it's removed.
+const MAX_CURVES: usize = 32;
+const SPAWN_LENGTH: usize = 100_000;
 
-    if state.position.0 >= 0
-        && state.position.1 >= 0
-        && state.position.0 < config.width
-        && state.position.1 < config.height
-    {
-        let rgba = to_rgba(color);
-        let i = ((state.position.0 + state.position.1 * config.width) * 4) as usize;
-        frame[i..(i + 4)].copy_from_slice(&rgba);
-    }
-    // update state
-    state.t += 1;
-    state.segment_progress += 1;
-
-    state.position.0 += state.direction.0;
-    state.position.1 += state.direction.1;
-    if state.segment_progress >= config.segment_length {
-        // bits that differ when you increment the turn counter
-        let bits = state.turn_counter ^ (state.turn_counter + 1);
-        // most significant bit
-        let bit = (bits + 1) >> 1;
-
-        let current_turn = if (state.turn_state & bit) != 0 {
-            Turn::L
-        } else {
-            Turn::R
-        };
-
-        state.turn_state ^= bit; // flip the bit
-        state.turn_counter += 1;
-        state.direction = turn(state.direction, current_turn);
-        state.segment_progress = 0;
-    }
-}
+// The scene to draw when none is given.
+const DEFAULT_SCENE: &str = include_str!("../../scenes/four-spirals.toml");
 
 fn main() {
     #[cfg(target_arch = "wasm32")]
@@ -199,10 +45,74 @@ fn main() {
     }
 }
 
+#[cfg(target_arch = "wasm32")]
+async fn load_scene() -> Result<Scene, SceneError> {
+    // `?scene=<url>` fetches a scene file instead of drawing the default one
+    let search = web_sys::window()
+        .unwrap()
+        .location()
+        .search()
+        .unwrap_or_default();
+    let scene_url = web_sys::UrlSearchParams::new_with_str(&search)
+        .ok()
+        .and_then(|params| params.get("scene"));
+    match scene_url {
+        Some(url) => {
+            let text = fetch_text(&url)
+                .await
+                .map_err(|e| SceneError::Io(std::io::Error::other(format!("{url}: {e}"))))?;
+            if url.ends_with(".json") {
+                Scene::from_json(&text)
+            } else {
+                Scene::from_toml(&text)
+            }
+        }
+        None => Scene::from_toml(DEFAULT_SCENE),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn fetch_text(url: &str) -> Result<String, String> {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    let window = web_sys::window().unwrap();
+    let response = JsFuture::from(window.fetch_with_str(url))
+        .await
+        .map_err(|e| format!("{e:?}"))?
+        .dyn_into::<web_sys::Response>()
+        .map_err(|e| format!("{e:?}"))?;
+    if !response.ok() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    let text = JsFuture::from(response.text().map_err(|e| format!("{e:?}"))?)
+        .await
+        .map_err(|e| format!("{e:?}"))?;
+    text.as_string()
+        .ok_or_else(|| "response body is not text".to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn load_scene() -> Result<Scene, SceneError> {
+    match std::env::args_os().nth(1) {
+        Some(path) => Scene::load(path),
+        None => Scene::from_toml(DEFAULT_SCENE),
+    }
+}
+
 async fn run() {
+    let scene = match load_scene().await {
+        Ok(scene) => scene,
+        Err(err) => {
+            log_error("load_scene", err);
+            return;
+        }
+    };
+    let (width, height) = (scene.width as u32, scene.height as u32);
+
     let event_loop = EventLoop::new();
     let window = {
-        let size = LogicalSize::new(WIDTH as f64, HEIGHT as f64);
+        let size = LogicalSize::new(width as f64, height as f64);
         WindowBuilder::new()
             .with_title("dragon curve, now on the web!")
             .with_inner_size(size)
@@ -261,66 +171,19 @@ async fn run() {
         let window_size = window.inner_size();
         let surface_texture =
             SurfaceTexture::new(window_size.width, window_size.height, window.as_ref());
-        Pixels::new_async(WIDTH, HEIGHT, surface_texture)
+        Pixels::new_async(width, height, surface_texture)
             .await
             .expect("Pixels error")
     };
 
-    let config = Config {
-        width: WIDTH.try_into().unwrap(),
-        height: HEIGHT.try_into().unwrap(),
-        segment_length: SEGMENT_LENGTH,
-    };
-
-    let screen_center: (isize, isize) = (
-        ((config.width / 2) as isize).try_into().unwrap(),
-        ((config.height / 2) as isize).try_into().unwrap(),
-    );
-
-    // initialize states
-    let mut states: Vec<State> = vec![
-        State::new(
-            screen_center,
-            (1, 0),
-            two_color_gradient(
-                (255.0 / 255.0, 0.0, 0.0),           // red
-                (255.0 / 255.0, 136.0 / 255.0, 0.0), // orange
-            ),
-            0,
-            50000,
-        ),
-        State::new(
-            screen_center,
-            (0, 1),
-            two_color_gradient(
-                (80.0 / 255.0, 0.0 / 255.0, 255.0 / 255.0), // blurple
-                (187.0 / 255.0, 0.0, 80.0 / 255.0),         // pinkish
-            ),
-            10000,
-            25000,
-        ),
-        State::new(
-            screen_center,
-            (-1, 0),
-            two_color_gradient(
-                (
-                    153.0 / 255.0 / 5.0,
-                    204.0 / 255.0 / 5.0,
-                    255.0 / 255.0 / 5.0,
-                ),
-                (0.0 / 255.0, 176.0 / 255.0, 240.0 / 255.0), // #00b0f0
-            ),
-            0,
-            50000,
-        ),
-        State::new(
-            screen_center,
-            (0, -1),
-            two_color_gradient((0.1, 0.1, 0.1), (0.6, 0.6, 0.6)),
-            10000,
-            25000,
-        ),
-    ];
+    let config = scene.config();
+    let mut states: Vec<State> = scene.states();
+    let mut density = scene
+        .tone_map()
+        .map(|_| Density::new(scene.width, scene.height));
+    let mut fade = scene.fade(BLACK);
+    let mut spawner = Spawner::new(MAX_CURVES, SPAWN_LENGTH);
+    let mut spawning_from: Option<(isize, isize)> = None;
 
     event_loop.run(move |event, _, control_flow| {
         // Draw the current frame
@@ -349,12 +212,52 @@ async fn run() {
                 }
             }
 
+            // Add a curve where the mouse was pressed, heading the way it was
+            // dragged, or the way the modifier keys pick for a click
+            let pixel = input
+                .mouse()
+                .map(|mouse| match pixels.window_pos_to_pixel(mouse) {
+                    Ok((x, y)) => (x as isize, y as isize),
+                    Err(outside) => outside,
+                });
+            if input.mouse_pressed(0) {
+                spawning_from = pixel;
+            }
+            if input.mouse_released(0) {
+                if let (Some(from), Some(to)) = (spawning_from.take(), pixel) {
+                    let direction =
+                        drag_direction((from.0 as f64, from.1 as f64), (to.0 as f64, to.1 as f64))
+                            .unwrap_or_else(|| {
+                                modifier_direction(
+                                    input.held_shift(),
+                                    input.held_control(),
+                                    input.held_alt(),
+                                )
+                            });
+                    if let Some(state) = spawner.spawn(states.len(), from, direction) {
+                        states.push(state);
+                    }
+                }
+            }
+
             // Update internal state
+            let mut framebuffer =
+                RgbaFramebuffer::new(pixels.frame_mut(), scene.width, scene.height);
             for _ in 0..BATCH_SIZE {
                 for state in states.iter_mut() {
-                    update(&config, pixels.frame_mut(), state);
+                    match &mut density {
+                        Some(density) => accumulate(&config, density, state),
+                        None => update(&config, &mut framebuffer, state),
+                    }
                 }
             }
+            if let (Some(density), Some(tone_map)) = (&density, scene.tone_map()) {
+                density.tone_map(tone_map, &mut framebuffer);
+            }
+            if let Some(fade) = &mut fade {
+                fade.advance(BATCH_SIZE, &mut framebuffer);
+            }
+            states.retain(|state| !state.finished());
             // and request a redraw
             window.request_redraw();
         }
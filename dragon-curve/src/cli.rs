@@ -0,0 +1,359 @@
+use clap::builder::PossibleValuesParser;
+use clap::{Parser, ValueEnum};
+use dragon_curve_core::scene::{
+    parse_direction, parse_point, BlendSpec, ColoringSpec, Curve, Direction, FoldsSpec,
+    GradientSpec, LSystemSpec, StrokeSpec, ToneMapSpec, Turns,
+};
+use dragon_curve_core::{
+    Blend, ColorMapping, ColorSpace, Extend, Folds, Gradient, LSystem, Scene, SceneError, Stroke,
+    ToneMap, LSYSTEM_NAMES,
+};
+use std::path::PathBuf;
+
+/// Animates dragon curves in a window.
+///
+/// The per-curve options (`--start`, `--direction`, `--gradient`,
+/// `--gradient-space`, `--gradient-extend`, `--coloring`, `--blend`,
+/// `--opacity`, `--stroke`, `--corner-radius`, `--countdown`, `--duration`,
+/// `--turns`, `--angle`, `--folds`, `--lsystem`, `--iterations`) can be given
+/// several times; curve `i` uses the `i`-th value, wrapping around if there
+/// are fewer values than curves.
+///
+/// `--turns`, `--folds` and `--lsystem` pick the kind of path for every
+/// curve at once, so only one of them can be given; the curves can still
+/// each follow a different turn sequence, fold pattern or L-system.
+#[derive(Parser)]
+pub struct Args {
+    /// Scene file (`.toml` or `.json`) describing the canvas and curves
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "segment_length",
+            "density",
+            "fade",
+            "curves",
+            "start",
+            "direction",
+            "gradient",
+            "gradient_space",
+            "gradient_extend",
+            "coloring",
+            "blend",
+            "opacity",
+            "stroke",
+            "corner_radius",
+            "countdown",
+            "duration",
+            "turns",
+            "angle",
+            "folds",
+            "lsystem",
+            "iterations",
+        ],
+    )]
+    pub scene: Option<PathBuf>,
+
+    /// Window width, overriding the scene's [default: 1200]
+    #[arg(long)]
+    pub width: Option<usize>,
+
+    /// Window height, overriding the scene's [default: 800]
+    #[arg(long)]
+    pub height: Option<usize>,
+
+    /// Number of pixels between turns
+    #[arg(long, default_value_t = 1)]
+    pub segment_length: usize,
+
+    /// Count visits and tone-map them into a heatmap instead of plotting
+    /// colors: `log`, `gamma:<gamma>` or `equalize`
+    #[arg(long, value_parser = parse_tone_map)]
+    pub density: Option<ToneMap>,
+
+    /// Number of steps for drawn pixels to fade halfway to the background,
+    /// leaving trails behind the curves
+    #[arg(long, conflicts_with = "density")]
+    pub fade: Option<f64>,
+
+    /// Number of pixels each curve advances per frame
+    #[arg(long, default_value_t = 1000)]
+    pub batch_size: usize,
+
+    /// Number of curves that can be going at once, counting the ones added
+    /// by clicking
+    #[arg(long, default_value_t = 32)]
+    pub max_curves: usize,
+
+    /// Number of pixels each curve added by clicking draws before it's removed
+    #[arg(long, default_value_t = 100_000)]
+    pub spawn_length: usize,
+
+    /// Number of curves to draw
+    #[arg(short = 'n', long, default_value_t = 4)]
+    pub curves: usize,
+
+    /// Starting position as `x,y` [default: the center of the window]
+    #[arg(long, value_parser = parse_point)]
+    pub start: Vec<(isize, isize)>,
+
+    /// Starting direction: `right`, `down`, `left`, `up` or `dx,dy`
+    /// [default: right, down, left, up]
+    #[arg(long, value_parser = parse_direction)]
+    pub direction: Vec<(isize, isize)>,
+
+    /// Gradient to color the curve with, a name like `viridis` or a list of
+    /// colors like `#ff0000, #ff8800 50%, #ff0000`
+    /// [default: red-orange, blurple-pink, blue, gray]
+    #[arg(long, value_parser = parse_gradient)]
+    pub gradient: Vec<Gradient>,
+
+    /// Color space to blend gradient colors in: `srgb`, `linear-rgb`,
+    /// `oklab`, `oklch` or `hsv` [default: the gradient's own]
+    #[arg(long, value_parser = parse_space)]
+    pub gradient_space: Vec<ColorSpace>,
+
+    /// What gradients do past their ends: `clamp`, `repeat` or `mirror`
+    /// [default: clamp]
+    #[arg(long, value_parser = parse_extend)]
+    pub gradient_extend: Vec<Extend>,
+
+    /// How pixels pick their gradient color: `log2`, `linear:<length>`,
+    /// `periodic:<period>`, `heading`, `fold-depth:<levels>`, `curve-index`
+    /// or `distance:<radius>` [default: log2]
+    #[arg(long, value_parser = parse_coloring)]
+    pub coloring: Vec<ColorMapping>,
+
+    /// How pixels mix with ones already drawn where curves cross: `replace`,
+    /// `add`, `over`, `multiply` or `max` [default: replace]
+    #[arg(long, value_parser = parse_blend)]
+    pub blend: Vec<Blend>,
+
+    /// How strongly pixels are blended in, from 0.0 to 1.0, with a `--blend`
+    /// other than `replace` [default: 1.0]
+    #[arg(long)]
+    pub opacity: Vec<f64>,
+
+    /// How steps are drawn: `pixels`, or `smooth:<width>` for anti-aliased
+    /// lines that stay smooth when zoomed [default: pixels]
+    #[arg(long, value_parser = parse_stroke)]
+    pub stroke: Vec<Stroke>,
+
+    /// Radius in pixels of the arcs rounding off each turn, so places where
+    /// the curve touches itself come apart [default: sharp corners]
+    #[arg(long)]
+    pub corner_radius: Vec<f64>,
+
+    /// Number of steps to wait before starting
+    #[arg(long)]
+    pub countdown: Vec<usize>,
+
+    /// Number of pixels to draw before restarting the curve, 0 for never
+    #[arg(long)]
+    pub duration: Vec<usize>,
+
+    /// Built-in turn sequence to follow, making every curve follow one
+    #[arg(long, value_enum, conflicts_with_all = ["folds", "lsystem"])]
+    pub turns: Vec<TurnsArg>,
+
+    /// Degrees to turn at each corner [default: the turn sequence's own]
+    #[arg(long)]
+    pub angle: Vec<f64>,
+
+    /// Fold pattern like `RLL` for a generalized paperfolding curve, making
+    /// every curve one
+    #[arg(long, value_parser = parse_folds, conflicts_with = "lsystem")]
+    pub folds: Vec<Folds>,
+
+    /// Draw a built-in L-system instead of the dragon curve
+    #[arg(long, value_parser = PossibleValuesParser::new(LSYSTEM_NAMES))]
+    pub lsystem: Vec<String>,
+
+    /// Number of rewriting iterations for `--lsystem`, at most 32 [default:
+    /// the L-system's own]
+    #[arg(long)]
+    pub iterations: Vec<usize>,
+}
+
+// mirrors `scene::Turns`, which can't derive `ValueEnum` itself
+#[derive(Clone, Copy, ValueEnum)]
+pub enum TurnsArg {
+    Dragon,
+    Terdragon,
+}
+
+impl From<TurnsArg> for Turns {
+    fn from(turns: TurnsArg) -> Turns {
+        match turns {
+            TurnsArg::Dragon => Turns::Dragon,
+            TurnsArg::Terdragon => Turns::Terdragon,
+        }
+    }
+}
+
+const DEFAULT_DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+const DEFAULT_GRADIENTS: [&str; 4] = ["red-orange", "blurple-pink", "blue", "gray"];
+
+/// Picks the `i`-th value, wrapping around, or `None` if there are none.
+fn nth<T: Clone>(values: &[T], i: usize) -> Option<T> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values[i % values.len()].clone())
+    }
+}
+
+impl Args {
+    /// Loads `--scene` if given, with `--width` and `--height` overriding its
+    /// size, otherwise builds a scene from the other options.
+    pub fn scene(&self) -> Result<Scene, SceneError> {
+        if let Some(path) = &self.scene {
+            let mut scene = Scene::load(path)?;
+            scene.width = self.width.unwrap_or(scene.width);
+            scene.height = self.height.unwrap_or(scene.height);
+            return scene.validate();
+        }
+        let curves = (0..self.curves)
+            .map(|i| {
+                let gradient = nth(&self.gradient, i)
+                    .unwrap_or_else(|| Gradient::named(DEFAULT_GRADIENTS[i % 4]).unwrap());
+                let gradient = match nth(&self.gradient_space, i) {
+                    Some(space) => gradient.with_space(space),
+                    None => gradient,
+                };
+                let gradient = match nth(&self.gradient_extend, i) {
+                    Some(extend) => gradient.with_extend(extend),
+                    None => gradient,
+                };
+                Curve {
+                    start: nth(&self.start, i),
+                    direction: Direction(
+                        nth(&self.direction, i).unwrap_or(DEFAULT_DIRECTIONS[i % 4]),
+                    ),
+                    gradient: GradientSpec(gradient),
+                    coloring: nth(&self.coloring, i).map(ColoringSpec),
+                    blend: nth(&self.blend, i).map(BlendSpec),
+                    opacity: nth(&self.opacity, i),
+                    stroke: nth(&self.stroke, i).map(StrokeSpec),
+                    corner_radius: nth(&self.corner_radius, i),
+                    countdown: nth(&self.countdown, i).unwrap_or(0),
+                    duration: nth(&self.duration, i).unwrap_or(0),
+                    turns: nth(&self.turns, i).map(Turns::from),
+                    angle: nth(&self.angle, i),
+                    folds: nth(&self.folds, i).map(FoldsSpec),
+                    lsystem: nth(&self.lsystem, i)
+                        .map(|name| LSystemSpec(LSystem::named(&name).unwrap())),
+                    iterations: nth(&self.iterations, i),
+                }
+            })
+            .collect();
+        Scene {
+            width: self.width.unwrap_or(1200),
+            height: self.height.unwrap_or(800),
+            segment_length: self.segment_length,
+            density: self.density.map(ToneMapSpec),
+            fade: self.fade,
+            curves,
+        }
+        .validate()
+    }
+}
+
+fn parse_folds(s: &str) -> Result<Folds, String> {
+    s.parse()
+}
+
+fn parse_gradient(s: &str) -> Result<Gradient, String> {
+    s.parse()
+}
+
+fn parse_space(s: &str) -> Result<ColorSpace, String> {
+    s.parse()
+}
+
+fn parse_extend(s: &str) -> Result<Extend, String> {
+    s.parse()
+}
+
+fn parse_coloring(s: &str) -> Result<ColorMapping, String> {
+    s.parse()
+}
+
+fn parse_blend(s: &str) -> Result<Blend, String> {
+    s.parse()
+}
+
+fn parse_tone_map(s: &str) -> Result<ToneMap, String> {
+    s.parse()
+}
+
+fn parse_stroke(s: &str) -> Result<Stroke, String> {
+    s.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dragon_curve_core::State;
+
+    fn parse(args: &[&str]) -> Result<Args, clap::Error> {
+        Args::try_parse_from(std::iter::once("dragon-curve").chain(args.iter().copied()))
+    }
+
+    fn states(args: &[&str]) -> Vec<State> {
+        parse(args).unwrap().scene().unwrap().states()
+    }
+
+    #[test]
+    fn defaults_to_four_curves_from_the_center() {
+        let states = states(&["--width", "100", "--height", "60"]);
+        let directions: Vec<_> = states.iter().map(State::direction).collect();
+        let expected = DEFAULT_DIRECTIONS.map(|(x, y)| (x as f64, y as f64));
+        assert_eq!(directions, expected);
+        assert!(states.iter().all(|state| state.position() == (50, 30)));
+    }
+
+    #[test]
+    fn wraps_per_curve_values() {
+        let states = states(&[
+            "-n",
+            "3",
+            "--direction",
+            "left",
+            "--start",
+            "10,20",
+            "--start",
+            "30,40",
+        ]);
+        let positions: Vec<_> = states.iter().map(State::position).collect();
+        assert_eq!(positions, [(10, 20), (30, 40), (10, 20)]);
+        assert!(states.iter().all(|state| state.direction() == (-1.0, 0.0)));
+    }
+
+    #[test]
+    fn picks_lsystems_per_curve() {
+        let scene = parse(&["-n", "2", "--lsystem", "hilbert", "--iterations", "3"])
+            .unwrap()
+            .scene()
+            .unwrap();
+        for curve in &scene.curves {
+            let Some(LSystemSpec(system)) = &curve.lsystem else {
+                panic!("expected an L-system");
+            };
+            assert_eq!(system.axiom, "A");
+            assert_eq!(curve.iterations, Some(3));
+        }
+        assert!(parse(&["--iterations", "3"]).unwrap().scene().is_err());
+        assert!(parse(&["--lsystem", "fern"]).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_options() {
+        assert!(parse(&["--direction", "2,0"]).is_err());
+        assert!(parse(&["--gradient", "plaid"]).is_err());
+        assert!(parse(&["--scene", "a.toml", "--countdown", "3"]).is_err());
+        assert!(parse(&["-n", "0"]).unwrap().scene().is_err());
+        // one kind of path for every curve
+        assert!(parse(&["--folds", "RLL", "--lsystem", "hilbert"]).is_err());
+        assert!(parse(&["--turns", "terdragon", "--folds", "RLL"]).is_err());
+    }
+}
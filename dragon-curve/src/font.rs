@@ -0,0 +1,124 @@
+//! A tiny 3x5 bitmap font for the status line.
+
+use dragon_curve_core::{Color, Framebuffer};
+
+pub const GLYPH_WIDTH: usize = 3;
+pub const GLYPH_HEIGHT: usize = 5;
+
+/// Rows of a character, top first, with the leftmost pixel in the highest of
+/// the three bits. Lowercase letters are drawn as uppercase.
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        _ => [0b111, 0b001, 0b010, 0b000, 0b010], // `?`
+    }
+}
+
+/// Width in pixels of `text` drawn at `scale`, with a pixel of space
+/// between characters.
+pub fn text_width(text: &str, scale: usize) -> usize {
+    text.chars().count() * (GLYPH_WIDTH + 1) * scale
+}
+
+/// Draws `text` with its top left corner at `position`, each font pixel
+/// `scale` pixels wide.
+pub fn draw_text(
+    framebuffer: &mut impl Framebuffer,
+    position: (isize, isize),
+    scale: usize,
+    text: &str,
+    color: Color,
+) {
+    let scale = scale as isize;
+    for (i, c) in text.chars().enumerate() {
+        let left = position.0 + i as isize * (GLYPH_WIDTH as isize + 1) * scale;
+        for (row, bits) in glyph(c).into_iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                let x = left + column as isize * scale;
+                let y = position.1 + row as isize * scale;
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        framebuffer.plot((x + dx, y + dy), color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dragon_curve_core::color::WHITE;
+    use dragon_curve_core::BgraFramebuffer;
+
+    #[test]
+    fn draws_scaled_glyphs() {
+        let (width, height) = (text_width("-1", 2), GLYPH_HEIGHT * 2);
+        assert_eq!(width, 16);
+        let mut buffer = vec![0; width * height];
+        let mut framebuffer = BgraFramebuffer::new(&mut buffer, width, height);
+        draw_text(&mut framebuffer, (0, 0), 2, "-1", WHITE);
+
+        let rows: Vec<String> = buffer
+            .chunks(width)
+            .step_by(2)
+            .map(|row| {
+                let pixel = |x: &u32| if *x == 0 { '.' } else { '#' };
+                row.iter().step_by(2).map(pixel).collect()
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [".....#..", "....##..", "###..#..", ".....#..", "....###."]
+        );
+    }
+}
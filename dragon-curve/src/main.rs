@@ -1,270 +1,158 @@
-use minifb::{Key, Window, WindowOptions};
+mod cli;
+mod font;
+mod viewer;
 
-const WIDTH: usize = 1200;
-const HEIGHT: usize = 800;
-const SEGMENT_LENGTH: usize = 1;
-const BATCH_SIZE: usize = 1000;
+use clap::Parser;
+use dragon_curve_core::color::{BLACK, WHITE};
+use dragon_curve_core::{
+    drag_direction, modifier_direction, BgraFramebuffer, Framebuffer, Spawner,
+};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
+use viewer::Viewer;
 
-// colors
-const WHITE: (f64, f64, f64) = (1.0, 1.0, 1.0);
-const RED: (f64, f64, f64) = (1.0, 0.0, 0.0);
-const YELLOW: (f64, f64, f64) = (1.0, 1.0, 0.0);
-const GREEN: (f64, f64, f64) = (0.0, 1.0, 0.0);
-const CYAN: (f64, f64, f64) = (0.0, 1.0, 1.0);
-const BLUE: (f64, f64, f64) = (0.0, 0.0, 1.0);
-const MAGENTA: (f64, f64, f64) = (1.0, 0.0, 1.0);
-
-pub struct State {
-    position: (isize, isize),  // pixel coordinates
-    direction: (isize, isize), // position + direction = next position
-    segment_progress: usize,   // number of pixels into a segment
-    t: usize,                  // number of pixels into the curve
-    turn_counter: i64,
-    turn_state: i64,
-}
-
-impl State {
-    fn new(direction: (isize, isize)) -> State {
-        return State {
-            position: (
-                ((WIDTH / 2) as isize).try_into().unwrap(),
-                ((HEIGHT / 2) as isize).try_into().unwrap(),
-            ),
-            direction: direction,
-            segment_progress: 0,
-            t: 0,
-            turn_counter: 0,
-            turn_state: 0,
-        };
-    }
-}
-
-#[derive(Clone, Copy)]
-pub struct GradientStop {
-    depth: f64,
-    color: (f64, f64, f64),
-}
-
-#[derive(Clone, Copy)]
-pub enum Turn {
-    L,
-    R,
-}
-
-fn turn(direction: (isize, isize), turn: Turn) -> (isize, isize) {
-    match turn {
-        Turn::L => (direction.1, -direction.0),
-        Turn::R => (-direction.1, direction.0),
-    }
-}
-
-fn to_bgra(color: (f64, f64, f64)) -> u32 {
-    let r: u32 = (color.0 * 255.99) as u32;
-    let g: u32 = (color.1 * 255.99) as u32;
-    let b: u32 = (color.2 * 255.99) as u32;
-    255 << 24 | r << 16 | g << 8 | b
-}
-
-fn lerp_f64(u: f64, v: f64, t: f64) -> f64 {
-    v * t + u * (1.0 - t)
-}
-
-fn lerp_color(u: (f64, f64, f64), v: (f64, f64, f64), t: f64) -> (f64, f64, f64) {
-    (
-        lerp_f64(u.0, v.0, t),
-        lerp_f64(u.1, v.1, t),
-        lerp_f64(u.2, v.2, t),
-    )
-}
-
-fn two_color_gradient(a: (f64, f64, f64), b: (f64, f64, f64)) -> Vec<GradientStop> {
-    vec![
-        GradientStop {
-            depth: 0.0,
-            color: a,
-        },
-        GradientStop {
-            depth: 1.0 / 2.0,
-            color: b,
-        },
-        GradientStop {
-            depth: 1.0,
-            color: a,
-        },
-    ]
-}
-
-fn get_gradient_color(gradient: &Vec<GradientStop>, depth: f64) -> (f64, f64, f64) {
-    for i in 1..gradient.len() {
-        if gradient[i].depth >= depth {
-            let t = (depth - gradient[i - 1].depth) / (gradient[i].depth - gradient[i - 1].depth);
-            return lerp_color(gradient[i - 1].color, gradient[i].color, t);
-        }
-    }
-    panic!("Invalid gradient depth: {:?}", depth);
-}
-
-fn update(
-    framebuffer: &mut Vec<u32>,
-    width: isize,
-    height: isize,
-    segment_length: usize,
-    state: &mut State,
-    gradient: &Vec<GradientStop>,
-) -> () {
-    // update framebuffer
-    let mut d = (state.t as f64 + 1.0).log2();
-    d -= d.floor();
-    let color = get_gradient_color(&gradient, d);
-    if state.position.0 >= 0
-        && state.position.1 >= 0
-        && state.position.0 < width
-        && state.position.1 < height
-    {
-        framebuffer[(state.position.0 + state.position.1 * width) as usize] = to_bgra(color);
-    }
-    // update state
-    state.t += 1;
-    state.segment_progress += 1;
-
-    state.position.0 += state.direction.0;
-    state.position.1 += state.direction.1;
-    if state.segment_progress >= segment_length {
-        // bits that differ when you increment the turn counter
-        let bits = state.turn_counter ^ (state.turn_counter + 1);
-        // most significant bit
-        let bit = (bits + 1) >> 1;
-
-        let current_turn = if (state.turn_state & bit) != 0 {
-            Turn::L
-        } else {
-            Turn::R
-        };
-
-        state.turn_state ^= bit; // flip the bit
-        state.turn_counter += 1;
-        state.direction = turn(state.direction, current_turn);
-        state.segment_progress = 0;
-    }
-}
+const HELP: &str = "space: pause  s: step  up/down: speed  r: reset  c: clear  g: gradients";
 
 fn main() {
+    let args = cli::Args::parse();
+    let scene = args.scene().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+    let (width, height) = (scene.width, scene.height);
+
     println!("Creating a window...");
     let mut window = Window::new(
-        "hello! - ESC to exit",
-        WIDTH,
-        HEIGHT,
+        "hello! - ESC to exit, drag to pan, scroll to zoom, right-click to add a curve",
+        width,
+        height,
         WindowOptions::default(),
     )
     .unwrap_or_else(|e| {
         panic!("{}", e);
     });
 
-    println!("Creating a framebuffer...");
-    let mut framebuffer = vec![0; WIDTH * HEIGHT];
-
     println!("Initializing state...");
-    let mut state = State::new((1, 0));
-    let mut state2 = State::new((0, 1));
-    let mut state3 = State::new((-1, 0));
-    let mut state4 = State::new((0, -1));
-
-    let _solid_gradient = vec![
-        GradientStop {
-            depth: 0.0,
-            color: WHITE,
-        },
-        GradientStop {
-            depth: 1.0,
-            color: WHITE,
-        },
-    ];
-
-    let _hsv_gradient = vec![
-        GradientStop {
-            depth: 0.0,
-            color: RED,
-        },
-        GradientStop {
-            depth: 1.0 / 6.0,
-            color: YELLOW,
-        },
-        GradientStop {
-            depth: 2.0 / 6.0,
-            color: GREEN,
-        },
-        GradientStop {
-            depth: 3.0 / 6.0,
-            color: CYAN,
-        },
-        GradientStop {
-            depth: 4.0 / 6.0,
-            color: BLUE,
-        },
-        GradientStop {
-            depth: 5.0 / 6.0,
-            color: MAGENTA,
-        },
-        GradientStop {
-            depth: 1.0,
-            color: RED,
-        },
-    ];
+    let spawner = Spawner::new(args.max_curves, args.spawn_length);
+    let mut viewer = Viewer::new(&scene, args.batch_size, spawner);
+    let mut screen = vec![0; width * height];
 
     println!("Opening a window...");
+    let mut paused = false;
+    let mut dragged_from: Option<(f32, f32)> = None;
+    let mut spawning_from: Option<(f32, f32)> = None;
+    // where the camera is headed, only redrawn through once it stops moving
+    let mut camera = viewer.camera();
     while window.is_open() && !window.is_key_down(Key::Escape) {
-        for _ in 0..BATCH_SIZE {
-            update(
-                &mut framebuffer,
-                WIDTH.try_into().unwrap(),
-                HEIGHT.try_into().unwrap(),
-                SEGMENT_LENGTH,
-                &mut state,
-                &two_color_gradient(
-                    RED,
-                    (255.0 / 255.0, 136.0 / 255.0, 0.0), // orange
-                ),
-            );
+        let mouse = window.get_mouse_pos(MouseMode::Pass);
+        if let (Some(mouse), true) = (mouse, window.get_mouse_down(MouseButton::Left)) {
+            if let Some(from) = dragged_from {
+                camera.pan(((mouse.0 - from.0) as f64, (mouse.1 - from.1) as f64));
+            }
+            dragged_from = Some(mouse);
+        } else {
+            dragged_from = None;
+        }
+        let scroll = window.get_scroll_wheel();
+        if let (Some(mouse), Some((_, scroll))) = (mouse, scroll) {
+            // one notch of the wheel zooms by 10%
+            let factor = 1.1f64.powf(scroll.clamp(-10.0, 10.0) as f64);
+            camera.zoom(factor, (mouse.0 as f64, mouse.1 as f64));
+        }
+        if dragged_from.is_none() && scroll.is_none() {
+            viewer.set_camera(camera);
+        }
 
-            update(
-                &mut framebuffer,
-                WIDTH.try_into().unwrap(),
-                HEIGHT.try_into().unwrap(),
-                SEGMENT_LENGTH,
-                &mut state2,
-                &two_color_gradient(
-                    (80.0 / 255.0, 0.0 / 255.0, 255.0 / 255.0), // blurple
-                    (187.0 / 255.0, 0.0, 80.0 / 255.0),         // pinkish
-                ),
-            );
+        // right-dragging picks the new curve's direction, modifiers do for a click
+        let right_down = window.get_mouse_down(MouseButton::Right);
+        match (mouse, right_down, spawning_from) {
+            (Some(mouse), true, None) => spawning_from = Some(mouse),
+            (Some(mouse), false, Some(from)) => {
+                let (from, to) = (
+                    (from.0 as f64, from.1 as f64),
+                    (mouse.0 as f64, mouse.1 as f64),
+                );
+                let direction = drag_direction(from, to).unwrap_or_else(|| {
+                    modifier_direction(
+                        window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift),
+                        window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl),
+                        window.is_key_down(Key::LeftAlt) || window.is_key_down(Key::RightAlt),
+                    )
+                });
+                let position = camera.to_world(from);
+                viewer.spawn(
+                    (position.0.floor() as isize, position.1.floor() as isize),
+                    direction,
+                );
+                spawning_from = None;
+            }
+            (None, _, _) => spawning_from = None,
+            _ => {}
+        }
+
+        for key in window.get_keys_pressed(KeyRepeat::No) {
+            match key {
+                Key::Space => paused = !paused,
+                Key::S | Key::Period => {
+                    paused = true;
+                    viewer.advance_by(scene.segment_length);
+                }
+                Key::Up | Key::Equal | Key::NumPadPlus => {
+                    viewer.set_batch_size(viewer.batch_size().saturating_mul(2))
+                }
+                Key::Down | Key::Minus | Key::NumPadMinus => {
+                    viewer.set_batch_size(viewer.batch_size() / 2)
+                }
+                Key::R => viewer.reset(),
+                Key::C => viewer.clear(),
+                Key::G => viewer.cycle_gradients(),
+                _ => {}
+            }
+        }
+        viewer.catch_up();
+        if !paused {
+            viewer.advance();
+        }
 
-            update(
-                &mut framebuffer,
-                WIDTH.try_into().unwrap(),
-                HEIGHT.try_into().unwrap(),
-                SEGMENT_LENGTH,
-                &mut state3,
-                &two_color_gradient(
-                    (
-                        153.0 / 255.0 / 5.0,
-                        204.0 / 255.0 / 5.0,
-                        255.0 / 255.0 / 5.0,
-                    ),
-                    (0.0 / 255.0, 176.0 / 255.0, 240.0 / 255.0), // 00b0f0
-                ),
-            );
+        if camera == viewer.camera() {
+            screen.copy_from_slice(viewer.buffer());
+        } else {
+            viewer.preview(camera, &mut screen);
+        }
+        let status = format!(
+            "step {}  speed {}/frame  curves {}/{}  {}  gradients: {}",
+            viewer.steps(),
+            viewer.batch_size(),
+            viewer.curves(),
+            viewer.max_curves(),
+            if paused { "paused" } else { "running" },
+            viewer.palette().unwrap_or("scene's"),
+        );
+        draw_status(
+            &mut BgraFramebuffer::new(&mut screen, width, height),
+            &[&status, HELP],
+        );
+        window.update_with_buffer(&screen, width, height).unwrap();
+    }
+}
 
-            update(
-                &mut framebuffer,
-                WIDTH.try_into().unwrap(),
-                HEIGHT.try_into().unwrap(),
-                SEGMENT_LENGTH,
-                &mut state4,
-                &two_color_gradient((0.1, 0.1, 0.1), (0.6, 0.6, 0.6)),
-            );
+/// Draws `lines` in the top left corner, on a black box so they show up over
+/// the curves.
+fn draw_status(framebuffer: &mut impl Framebuffer, lines: &[&str]) {
+    const SCALE: usize = 2;
+    const MARGIN: usize = 4;
+    let line_height = (font::GLYPH_HEIGHT + 2) * SCALE;
+    let text_width = lines
+        .iter()
+        .map(|line| font::text_width(line, SCALE))
+        .max()
+        .unwrap_or(0);
+    for y in 0..lines.len() * line_height + MARGIN * 2 {
+        for x in 0..text_width + MARGIN * 2 {
+            framebuffer.plot((x as isize, y as isize), BLACK);
         }
-        window
-            .update_with_buffer(&framebuffer, WIDTH, HEIGHT)
-            .unwrap();
+    }
+    for (i, line) in lines.iter().enumerate() {
+        let position = (MARGIN as isize, (MARGIN + i * line_height) as isize);
+        font::draw_text(framebuffer, position, SCALE, line, WHITE);
     }
 }
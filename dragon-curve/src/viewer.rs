@@ -0,0 +1,483 @@
+use dragon_curve_core::color::{to_bgra, BLACK};
+use dragon_curve_core::{
+    accumulate_in_view, update_in_view, BgraFramebuffer, Camera, Config, Density, Fade,
+    Framebuffer, Gradient, Scene, Spawner, State, ToneMap, GRADIENT_NAMES,
+};
+use std::collections::VecDeque;
+
+/// Most steps redrawn in a frame after the camera moves. Redrawing a long run
+/// takes as long as drawing it did, so it's spread over as many frames as it
+/// takes instead of holding one up.
+const REPLAY_STEPS: usize = 1 << 14;
+
+/// The curves on screen, along with what it takes to draw them again from the
+/// start when the camera moves.
+pub struct Viewer {
+    config: Config,
+    initial: Vec<State>, // including clicked ones, counting down to when they were added
+    states: Vec<(usize, State)>, // the ones that haven't finished yet, by index into `initial`
+    cleared: Vec<(usize, State)>, // what `states` was when the buffer was last cleared
+    replaying: VecDeque<(usize, usize)>, // runs of batches still to redraw after a camera move
+    spawner: Spawner,
+    scene_curves: usize, // number of curves in `initial` that came from the scene
+    width: usize,
+    height: usize,
+    buffer: Vec<u32>,
+    camera: Camera,
+    density: Option<(Density, ToneMap)>,
+    fade: Option<Fade>,
+    batch_size: usize,            // number of steps drawn between frames
+    steps: usize,                 // number of steps every curve has taken
+    cleared_at: usize,            // number of steps taken when the buffer was last cleared
+    batches: Vec<(usize, usize)>, // runs of batch sizes drawn since then, and their lengths
+    palette: Option<usize>,       // index into `GRADIENT_NAMES` of the first curve's gradient
+}
+
+impl Viewer {
+    pub fn new(scene: &Scene, batch_size: usize, spawner: Spawner) -> Viewer {
+        let (width, height) = (scene.width, scene.height);
+        let initial = scene.states();
+        Viewer {
+            config: scene.config(),
+            states: initial.iter().cloned().enumerate().collect(),
+            cleared: initial.iter().cloned().enumerate().collect(),
+            replaying: VecDeque::new(),
+            initial,
+            spawner,
+            scene_curves: scene.curves.len(),
+            width,
+            height,
+            buffer: vec![0; width * height],
+            camera: Camera::default(),
+            density: scene
+                .tone_map()
+                .map(|tone_map| (Density::new(width, height), tone_map)),
+            fade: scene.fade(BLACK),
+            batch_size,
+            steps: 0,
+            cleared_at: 0,
+            batches: Vec::new(),
+            palette: None,
+        }
+    }
+
+    pub fn buffer(&self) -> &[u32] {
+        &self.buffer
+    }
+
+    pub fn camera(&self) -> Camera {
+        self.camera
+    }
+
+    /// Number of curves that haven't finished yet.
+    pub fn curves(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn max_curves(&self) -> usize {
+        self.spawner.max_curves()
+    }
+
+    /// Number of steps every curve has taken, counting the ones still to be
+    /// redrawn after a camera move.
+    pub fn steps(&self) -> usize {
+        self.steps + self.behind()
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    pub fn set_batch_size(&mut self, batch_size: usize) {
+        self.batch_size = batch_size.max(1);
+    }
+
+    /// Name of the gradient the first curve was last switched to, or `None`
+    /// if the curves still have the scene's.
+    pub fn palette(&self) -> Option<&'static str> {
+        self.palette.map(|i| GRADIENT_NAMES[i])
+    }
+
+    /// Moves every curve a batch along and gets the buffer ready to show.
+    pub fn advance(&mut self) {
+        self.advance_by(self.batch_size);
+    }
+
+    /// Moves every curve `steps` pixels along and gets the buffer ready to
+    /// show. The curves wait while [`Viewer::catch_up`] still has steps to
+    /// redraw.
+    pub fn advance_by(&mut self, steps: usize) {
+        if self.replaying.is_empty() {
+            self.step(steps);
+            self.tone_map();
+        }
+    }
+
+    /// Redraws some more of what was drawn before the camera last moved, up
+    /// to [`REPLAY_STEPS`] steps or a single batch, whichever is more.
+    pub fn catch_up(&mut self) {
+        let mut budget = REPLAY_STEPS;
+        let mut drawn = false;
+        while let Some((size, count)) = self.replaying.front_mut() {
+            let size = *size;
+            if drawn && size > budget {
+                break;
+            }
+            *count -= 1;
+            if *count == 0 {
+                self.replaying.pop_front();
+            }
+            // in the same batches as the first time, so fading comes out the same
+            self.step(size);
+            budget = budget.saturating_sub(size);
+            drawn = true;
+        }
+        if drawn {
+            self.tone_map();
+        }
+    }
+
+    /// Starts the scene's curves over on an empty buffer, dropping the ones
+    /// added by clicking.
+    pub fn reset(&mut self) {
+        self.initial.truncate(self.scene_curves);
+        self.restart();
+        self.tone_map();
+    }
+
+    /// Empties the buffer, leaving the curves where they are.
+    pub fn clear(&mut self) {
+        // nothing left to redraw, but the curves still have to get there
+        let behind = self.behind();
+        for (_, state) in self.states.iter_mut() {
+            state.skip(&self.config, behind);
+        }
+        self.states.retain(|(_, state)| !state.finished());
+        self.steps += behind;
+        self.replaying.clear();
+
+        self.clear_buffer();
+        self.cleared = self.states.clone();
+        self.cleared_at = self.steps;
+        self.batches.clear();
+    }
+
+    /// Starts a new curve at `position` in world coordinates, unless there
+    /// are already as many curves as the spawner allows.
+    pub fn spawn(&mut self, position: (isize, isize), direction: (isize, isize)) {
+        if let Some(state) = self.spawner.spawn(self.states.len(), position, direction) {
+            let (steps, behind) = (self.steps(), self.behind());
+            self.initial.push(state.clone().with_countdown(steps));
+            let i = self.initial.len() - 1;
+            let cleared = state.clone().with_countdown(steps - self.cleared_at);
+            self.cleared.push((i, cleared));
+            self.states.push((i, state.with_countdown(behind)));
+        }
+    }
+
+    /// Switches every curve to the next built-in gradient, curve `i` taking
+    /// the `i`-th one after the first curve's.
+    pub fn cycle_gradients(&mut self) {
+        let palette = self.palette.map_or(0, |i| (i + 1) % GRADIENT_NAMES.len());
+        self.palette = Some(palette);
+        let gradient = |i: usize| {
+            Gradient::named(GRADIENT_NAMES[(palette + i) % GRADIENT_NAMES.len()]).unwrap()
+        };
+        for (i, state) in self.initial.iter_mut().enumerate() {
+            state.set_gradient(gradient(i));
+        }
+        for (i, state) in self.states.iter_mut().chain(self.cleared.iter_mut()) {
+            state.set_gradient(gradient(*i));
+        }
+    }
+
+    /// Draws the buffer into `screen` roughly as it would look through
+    /// `camera`, by moving and scaling what's already been drawn. Much
+    /// cheaper than [`Viewer::set_camera`], for while the camera is moving.
+    pub fn preview(&self, camera: Camera, screen: &mut [u32]) {
+        let background = to_bgra(BLACK);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let world = camera.to_world((x as f64 + 0.5, y as f64 + 0.5));
+                let (from_x, from_y) = self.camera.to_screen(world);
+                let inside = (0.0..self.width as f64).contains(&from_x)
+                    && (0.0..self.height as f64).contains(&from_y);
+                screen[x + y * self.width] = if inside {
+                    self.buffer[from_x as usize + from_y as usize * self.width]
+                } else {
+                    background
+                };
+            }
+        }
+    }
+
+    /// Looks through `camera` from now on, starting to redraw everything
+    /// since the buffer was last cleared through it. The redrawing is spread
+    /// over the following calls to [`Viewer::catch_up`], and still takes as
+    /// long as drawing it did the first time, so it's best left until the
+    /// camera stops moving.
+    pub fn set_camera(&mut self, camera: Camera) {
+        if camera == self.camera {
+            return;
+        }
+        self.camera = camera;
+
+        let mut batches = std::mem::take(&mut self.batches);
+        batches.extend(self.replaying.drain(..));
+        self.states = self.cleared.clone();
+        self.steps = self.cleared_at;
+        self.clear_buffer();
+        self.replaying = batches.into();
+        self.tone_map();
+    }
+
+    /// Number of steps still to redraw after a camera move.
+    fn behind(&self) -> usize {
+        self.replaying
+            .iter()
+            .map(|(size, count)| size * count)
+            .sum()
+    }
+
+    fn restart(&mut self) {
+        self.states = self.initial.iter().cloned().enumerate().collect();
+        self.cleared = self.states.clone();
+        self.steps = 0;
+        self.cleared_at = 0;
+        self.batches.clear();
+        self.replaying.clear();
+        self.clear_buffer();
+    }
+
+    fn clear_buffer(&mut self) {
+        BgraFramebuffer::new(&mut self.buffer, self.width, self.height).clear(BLACK);
+        if let Some((density, _)) = &mut self.density {
+            density.clear();
+        }
+        if let Some(fade) = &mut self.fade {
+            fade.reset();
+        }
+    }
+
+    fn step(&mut self, steps: usize) {
+        let mut framebuffer = BgraFramebuffer::new(&mut self.buffer, self.width, self.height);
+        for _ in 0..steps {
+            for (_, state) in self.states.iter_mut() {
+                match &mut self.density {
+                    Some((density, _)) => {
+                        accumulate_in_view(&self.config, &self.camera, density, state)
+                    }
+                    None => update_in_view(&self.config, &self.camera, &mut framebuffer, state),
+                }
+            }
+        }
+        if let Some(fade) = &mut self.fade {
+            fade.advance(steps, &mut framebuffer);
+        }
+        self.states.retain(|(_, state)| !state.finished());
+        self.steps += steps;
+        match self.batches.last_mut() {
+            Some((size, count)) if *size == steps => *count += 1,
+            _ => self.batches.push((steps, 1)),
+        }
+    }
+
+    fn tone_map(&mut self) {
+        if let Some((density, tone_map)) = &self.density {
+            let mut framebuffer = BgraFramebuffer::new(&mut self.buffer, self.width, self.height);
+            density.tone_map(*tone_map, &mut framebuffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewer() -> Viewer {
+        let scene = Scene::from_toml(
+            r#"
+            width = 40
+            height = 30
+
+            [[curves]]
+            direction = "right"
+            gradient = "hsv"
+            "#,
+        )
+        .unwrap();
+        let mut viewer = Viewer::new(&scene, 10, Spawner::new(2, 25));
+        viewer.reset();
+        viewer
+    }
+
+    fn caught_up(viewer: &mut Viewer) {
+        while !viewer.replaying.is_empty() {
+            viewer.catch_up();
+        }
+    }
+
+    fn lit(viewer: &Viewer) -> usize {
+        viewer
+            .buffer()
+            .iter()
+            .filter(|&&pixel| pixel != 0xff000000)
+            .count()
+    }
+
+    #[test]
+    fn resets_to_the_start() {
+        let mut viewer = viewer();
+        let fresh = viewer.buffer().to_vec();
+        assert_eq!(lit(&viewer), 0);
+        viewer.advance();
+        assert_eq!(viewer.steps(), 10);
+        assert!(lit(&viewer) > 0);
+        viewer.reset();
+        assert_eq!(viewer.steps(), 0);
+        assert!(viewer.buffer() == fresh);
+    }
+
+    #[test]
+    fn clearing_keeps_the_curves_going() {
+        let mut viewer = viewer();
+        viewer.advance();
+        viewer.clear();
+        assert_eq!(lit(&viewer), 0);
+        viewer.advance();
+        assert_eq!(viewer.steps(), 20);
+        let drawn = viewer.buffer().to_vec();
+        assert!(lit(&viewer) > 0);
+
+        // moving the camera doesn't bring back what was cleared
+        let mut camera = viewer.camera();
+        camera.pan((1.0, 0.0));
+        viewer.set_camera(camera);
+        caught_up(&mut viewer);
+        camera.pan((-1.0, 0.0));
+        viewer.set_camera(camera);
+        caught_up(&mut viewer);
+        assert!(viewer.buffer() == drawn);
+    }
+
+    #[test]
+    fn cycles_through_the_built_in_gradients() {
+        let mut viewer = viewer();
+        assert_eq!(viewer.palette(), None);
+        viewer.cycle_gradients();
+        assert_eq!(viewer.palette(), Some(GRADIENT_NAMES[0]));
+        for _ in 0..GRADIENT_NAMES.len() {
+            viewer.cycle_gradients();
+        }
+        assert_eq!(viewer.palette(), Some(GRADIENT_NAMES[0]));
+        viewer.set_batch_size(0);
+        assert_eq!(viewer.batch_size(), 1);
+    }
+
+    #[test]
+    fn spawns_curves_until_full() {
+        let mut viewer = viewer();
+        viewer.advance();
+        viewer.spawn((5, 5), (0, 1));
+        viewer.spawn((30, 5), (0, 1));
+        assert_eq!((viewer.curves(), viewer.max_curves()), (2, 2));
+
+        // the clicked curve is dropped once it has drawn its length
+        viewer.advance();
+        let drawn = viewer.buffer().to_vec();
+        viewer.advance_by(15);
+        assert_eq!(viewer.curves(), 1);
+        viewer.spawn((30, 5), (0, 1));
+        assert_eq!(viewer.curves(), 2);
+
+        viewer.reset();
+        assert_eq!(viewer.curves(), 1);
+        assert!(viewer.buffer() != drawn);
+    }
+
+    #[test]
+    fn redraws_clicked_curves_from_when_they_were_added() {
+        let mut viewer = viewer();
+        viewer.advance();
+        viewer.spawn((5, 5), (0, 1));
+        viewer.advance();
+        let drawn = viewer.buffer().to_vec();
+
+        let mut camera = viewer.camera();
+        camera.pan((3.0, 0.0));
+        viewer.set_camera(camera);
+        caught_up(&mut viewer);
+        camera.pan((-3.0, 0.0));
+        viewer.set_camera(camera);
+        caught_up(&mut viewer);
+        assert!(viewer.buffer() == drawn);
+    }
+
+    #[test]
+    fn redraws_a_bounded_number_of_steps_a_frame() {
+        let mut viewer = viewer();
+        viewer.set_batch_size(1000);
+        for _ in 0..100 {
+            viewer.advance();
+        }
+        let drawn = viewer.buffer().to_vec();
+
+        let mut camera = viewer.camera();
+        camera.pan((2.0, 0.0));
+        viewer.set_camera(camera);
+        assert_eq!((viewer.steps, viewer.steps()), (0, 100_000));
+        // the curves wait for the redrawing to catch up
+        viewer.advance();
+        assert_eq!(viewer.steps(), 100_000);
+        let mut frames = 0;
+        while !viewer.replaying.is_empty() {
+            let before = viewer.steps;
+            viewer.catch_up();
+            assert!(viewer.steps - before <= REPLAY_STEPS);
+            frames += 1;
+        }
+        assert_eq!(frames, 100_000usize.div_ceil(REPLAY_STEPS));
+        camera.pan((-2.0, 0.0));
+        viewer.set_camera(camera);
+        caught_up(&mut viewer);
+        assert!(viewer.buffer() == drawn);
+
+        camera.pan((2.0, 0.0));
+        viewer.set_camera(camera);
+        // clearing part way through skips the rest of the redrawing
+        viewer.catch_up();
+        viewer.clear();
+        assert_eq!((viewer.steps, viewer.steps()), (100_000, 100_000));
+        caught_up(&mut viewer);
+        assert_eq!(lit(&viewer), 0);
+        viewer.advance();
+        assert!(viewer.buffer() != drawn);
+    }
+
+    #[test]
+    fn spawns_while_redrawing() {
+        let mut viewer = viewer();
+        viewer.set_batch_size(REPLAY_STEPS);
+        viewer.advance();
+        viewer.advance();
+        viewer.set_batch_size(10);
+
+        // spawned mid-redraw, the curve starts from where the others really are
+        let mut camera = viewer.camera();
+        camera.pan((1.0, 0.0));
+        viewer.set_camera(camera);
+        viewer.catch_up();
+        viewer.spawn((5, 5), (0, 1));
+        caught_up(&mut viewer);
+        viewer.advance();
+        let drawn = viewer.buffer().to_vec();
+
+        camera.pan((-1.0, 0.0));
+        viewer.set_camera(camera);
+        caught_up(&mut viewer);
+        camera.pan((1.0, 0.0));
+        viewer.set_camera(camera);
+        caught_up(&mut viewer);
+        assert!(viewer.buffer() == drawn);
+    }
+}